@@ -0,0 +1,365 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::rpc::method_scopes;
+use crate::secret_file::write_secret_file;
+
+// トークンを保存するファイル名（アプリのデータディレクトリに保存）
+const AUTH_FILE_NAME: &str = "auth-token.json";
+
+// 共有トークン（すべてのメソッドを呼び出せる）のID
+pub const DEFAULT_TOKEN_ID: &str = "default";
+
+// すべてのメソッドを許可するスコープ
+pub const SCOPE_ALL: &str = "*";
+
+// 名前付きAPIトークン
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub token: String,
+    pub scopes: Vec<String>,
+    pub created_at: u64,
+}
+
+// 一覧表示用のAPIトークン情報（トークン値は含まない）
+#[derive(Serialize, Debug, Clone)]
+pub struct ApiTokenInfo {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: u64,
+}
+
+impl From<&ApiToken> for ApiTokenInfo {
+    fn from(token: &ApiToken) -> Self {
+        Self {
+            id: token.id.clone(),
+            name: token.name.clone(),
+            scopes: token.scopes.clone(),
+            created_at: token.created_at,
+        }
+    }
+}
+
+// 認証設定（トークンファイルの内容。スコープ付きトークンの値を含むため外部には返さない）
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct AuthSettings {
+    pub enabled: bool,
+    pub token: String,
+    #[serde(default)]
+    pub tokens: Vec<ApiToken>,
+}
+
+// get_auth_token で返す認証の状態（共有トークンのみ。スコープ付きトークンの値は作成時にだけ返す）
+#[derive(Serialize, Debug, Clone)]
+pub struct AuthStatus {
+    pub enabled: bool,
+    pub token: String,
+}
+
+// 認証失敗の理由
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuthError {
+    MissingToken,
+    InvalidToken,
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::MissingToken => write!(f, "Missing bearer token"),
+            AuthError::InvalidToken => write!(f, "Invalid bearer token"),
+        }
+    }
+}
+
+// 認証済みリクエストの呼び出し元と許可されたスコープ
+// デフォルトはどのメソッドも呼び出せない
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuthContext {
+    pub token_id: Option<String>,
+    pub scopes: Vec<String>,
+}
+
+impl AuthContext {
+    // すべてのメソッドを呼び出せるコンテキスト
+    pub fn full_access(token_id: Option<String>) -> Self {
+        Self {
+            token_id,
+            scopes: vec![SCOPE_ALL.to_string()],
+        }
+    }
+
+    // メソッドの呼び出しが許可されているか
+    pub fn allows(&self, method: &str) -> bool {
+        self.scopes.iter().any(|scope| scope_allows(scope, method))
+    }
+}
+
+// スコープがメソッドを許可するか（"*"、グループ名、メソッド名のいずれか）
+// メソッドが属するスコープグループは rpc::METHODS で定義する
+fn scope_allows(scope: &str, method: &str) -> bool {
+    if scope == SCOPE_ALL || scope == method {
+        return true;
+    }
+
+    method_scopes().any(|(name, group)| name == method && group == scope)
+}
+
+// 既知のスコープかどうか
+fn is_known_scope(scope: &str) -> bool {
+    scope == SCOPE_ALL || method_scopes().any(|(name, group)| name == scope || group == scope)
+}
+
+// Bearerトークン認証
+pub struct TokenAuth {
+    settings: Mutex<AuthSettings>,
+    file_path: Option<PathBuf>,
+}
+
+impl TokenAuth {
+    // 保存先を持たない無効状態の認証（アプリ初期化前のデフォルト）
+    pub fn disabled() -> Self {
+        Self {
+            settings: Mutex::new(AuthSettings {
+                enabled: false,
+                token: generate_token(),
+                tokens: Vec::new(),
+            }),
+            file_path: None,
+        }
+    }
+
+    // 保存先を持たない有効状態の認証（トークンファイルを読み込めない場合に使い、壊れたファイルは上書きしない）
+    pub fn unsaved() -> Self {
+        Self {
+            settings: Mutex::new(AuthSettings {
+                enabled: true,
+                token: generate_token(),
+                tokens: Vec::new(),
+            }),
+            file_path: None,
+        }
+    }
+
+    // トークンファイルを読み込む。存在しない場合は新しいトークンを生成して保存
+    pub fn load(app_data_dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let file_path = app_data_dir.join(AUTH_FILE_NAME);
+
+        let settings = if file_path.exists() {
+            let file_content = fs::read_to_string(&file_path)?;
+            serde_json::from_str(&file_content)?
+        } else {
+            AuthSettings {
+                enabled: true,
+                token: generate_token(),
+                tokens: Vec::new(),
+            }
+        };
+
+        let auth = Self {
+            settings: Mutex::new(settings),
+            file_path: Some(file_path),
+        };
+        auth.save()?;
+
+        Ok(auth)
+    }
+
+    pub fn status(&self) -> AuthStatus {
+        let settings = self.settings.lock().unwrap();
+        AuthStatus {
+            enabled: settings.enabled,
+            token: settings.token.clone(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.settings.lock().unwrap().enabled
+    }
+
+    // 新しいトークンを生成して保存し、認証を有効にする
+    pub fn rotate(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let token = generate_token();
+        {
+            let mut settings = self.settings.lock().unwrap();
+            settings.token = token.clone();
+            settings.enabled = true;
+        }
+        self.save()?;
+        Ok(token)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) -> Result<(), Box<dyn std::error::Error>> {
+        self.settings.lock().unwrap().enabled = enabled;
+        self.save()
+    }
+
+    // スコープを限定した名前付きトークンを作成（トークン値は作成時のみ返す）
+    pub fn create_token(&self, name: String, scopes: Vec<String>) -> Result<ApiToken, AppError> {
+        if name.trim().is_empty() {
+            return Err(AppError::validation(Some("name"), "Token name must not be empty"));
+        }
+        if scopes.is_empty() {
+            return Err(AppError::validation(Some("scopes"), "At least one scope is required"));
+        }
+        if let Some(unknown) = scopes.iter().find(|scope| !is_known_scope(scope)) {
+            return Err(AppError::validation(Some("scopes"), format!("Unknown scope: {}", unknown)));
+        }
+
+        let api_token = ApiToken {
+            id: Uuid::new_v4().to_string(),
+            name,
+            token: generate_token(),
+            scopes,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+
+        self.settings.lock().unwrap().tokens.push(api_token.clone());
+        self.save().map_err(AppError::storage)?;
+
+        Ok(api_token)
+    }
+
+    pub fn list_tokens(&self) -> Vec<ApiTokenInfo> {
+        let settings = self.settings.lock().unwrap();
+        settings.tokens.iter().map(ApiTokenInfo::from).collect()
+    }
+
+    pub fn revoke_token(&self, id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let removed = {
+            let mut settings = self.settings.lock().unwrap();
+            let before = settings.tokens.len();
+            settings.tokens.retain(|token| token.id != id);
+            settings.tokens.len() != before
+        };
+
+        if removed {
+            self.save()?;
+        }
+
+        Ok(removed)
+    }
+
+    // Authorization ヘッダーの値を検証し、呼び出し元のコンテキストを返す
+    pub fn verify(&self, authorization: Option<&str>) -> Result<AuthContext, AuthError> {
+        let settings = self.settings.lock().unwrap();
+        if !settings.enabled {
+            return Ok(AuthContext::full_access(None));
+        }
+
+        let token = authorization
+            .and_then(parse_bearer)
+            .ok_or(AuthError::MissingToken)?;
+
+        if constant_time_eq(token.as_bytes(), settings.token.as_bytes()) {
+            return Ok(AuthContext::full_access(Some(DEFAULT_TOKEN_ID.to_string())));
+        }
+
+        settings
+            .tokens
+            .iter()
+            .find(|api_token| constant_time_eq(token.as_bytes(), api_token.token.as_bytes()))
+            .map(|api_token| AuthContext {
+                token_id: Some(api_token.id.clone()),
+                scopes: api_token.scopes.clone(),
+            })
+            .ok_or(AuthError::InvalidToken)
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(file_path) = &self.file_path {
+            let json = serde_json::to_string_pretty(&*self.settings.lock().unwrap())?;
+            // トークンファイルは現在のユーザーのみ読み書き可能にする
+            write_secret_file(file_path, json.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+// "Bearer <token>" 形式からトークン部分を取り出す
+fn parse_bearer(value: &str) -> Option<&str> {
+    let (scheme, token) = value.trim().split_once(' ')?;
+    if scheme.eq_ignore_ascii_case("bearer") && !token.trim().is_empty() {
+        Some(token.trim())
+    } else {
+        None
+    }
+}
+
+// タイミング攻撃を避けるための比較
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// UUID v4 を2つ連結したランダムなトークンを生成（244ビットのランダム性）
+fn generate_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_bearer_token() {
+        let auth = TokenAuth::disabled();
+        auth.set_enabled(true).unwrap();
+        let token = auth.status().token;
+
+        assert_eq!(auth.verify(None), Err(AuthError::MissingToken));
+        assert_eq!(auth.verify(Some("Basic abc")), Err(AuthError::MissingToken));
+        assert_eq!(auth.verify(Some("Bearer wrong")), Err(AuthError::InvalidToken));
+        assert_eq!(
+            auth.verify(Some(&format!("Bearer {}", token))),
+            Ok(AuthContext::full_access(Some(DEFAULT_TOKEN_ID.to_string())))
+        );
+    }
+
+    #[test]
+    fn test_disabled_auth_allows_everything() {
+        let auth = TokenAuth::disabled();
+        assert!(auth.verify(None).unwrap().allows("notes_delete"));
+    }
+
+    #[test]
+    fn test_scoped_token() {
+        let auth = TokenAuth::disabled();
+        auth.set_enabled(true).unwrap();
+        let api_token = auth
+            .create_token("dashboard".to_string(), vec!["notes:read".to_string()])
+            .unwrap();
+
+        let context = auth.verify(Some(&format!("Bearer {}", api_token.token))).unwrap();
+        assert_eq!(context.token_id, Some(api_token.id.clone()));
+        assert!(context.allows("notes_list"));
+        assert!(context.allows("notes_get"));
+        assert!(!context.allows("notes_delete"));
+        assert!(!context.allows("system_info"));
+
+        assert!(auth.revoke_token(&api_token.id).unwrap());
+        assert_eq!(
+            auth.verify(Some(&format!("Bearer {}", api_token.token))),
+            Err(AuthError::InvalidToken)
+        );
+    }
+
+    #[test]
+    fn test_unknown_scope_is_rejected() {
+        let auth = TokenAuth::disabled();
+        assert!(auth.create_token("bad".to_string(), vec!["notes:admin".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,641 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+
+use crate::logs::DEFAULT_LOG_MEMORY_ENTRIES;
+use crate::redact::DEFAULT_REDACTED_HEADERS;
+
+// 設定ファイル名（アプリのデータディレクトリに保存）
+const CONFIG_FILE_NAME: &str = "server-config.json";
+
+// IPCソケットのデフォルト名（Unixではアプリのデータディレクトリに作成）
+#[cfg(unix)]
+const IPC_SOCKET_NAME: &str = "rpc.sock";
+#[cfg(windows)]
+const IPC_PIPE_NAME: &str = r"\\.\pipe\app-with-rpc";
+
+// 環境変数名の定数
+const ENV_HOST: &str = "RPC_SERVER_HOST";
+const ENV_PORT: &str = "RPC_SERVER_PORT";
+const ENV_PORT_RANGE_END: &str = "RPC_SERVER_PORT_RANGE_END";
+const ENV_EPHEMERAL_FALLBACK: &str = "RPC_SERVER_EPHEMERAL_FALLBACK";
+const ENV_ALLOWED_ORIGINS: &str = "RPC_SERVER_ALLOWED_ORIGINS";
+const ENV_THREADS: &str = "RPC_SERVER_THREADS";
+const ENV_WS_ENABLED: &str = "RPC_SERVER_WS_ENABLED";
+const ENV_WS_PORT: &str = "RPC_SERVER_WS_PORT";
+const ENV_HTTP_ENABLED: &str = "RPC_SERVER_HTTP_ENABLED";
+const ENV_IPC_ENABLED: &str = "RPC_SERVER_IPC_ENABLED";
+const ENV_IPC_PATH: &str = "RPC_SERVER_IPC_PATH";
+const ENV_MAX_BATCH_SIZE: &str = "RPC_SERVER_MAX_BATCH_SIZE";
+const ENV_MAX_BODY_BYTES: &str = "RPC_SERVER_MAX_BODY_BYTES";
+const ENV_RATE_LIMIT_PER_SEC: &str = "RPC_SERVER_RATE_LIMIT_PER_SEC";
+const ENV_MAX_IN_FLIGHT: &str = "RPC_SERVER_MAX_IN_FLIGHT";
+const ENV_SHUTDOWN_TIMEOUT_SECS: &str = "RPC_SERVER_SHUTDOWN_TIMEOUT_SECS";
+const ENV_TLS_ENABLED: &str = "RPC_SERVER_TLS_ENABLED";
+const ENV_TLS_CERT_PATH: &str = "RPC_SERVER_TLS_CERT_PATH";
+const ENV_TLS_KEY_PATH: &str = "RPC_SERVER_TLS_KEY_PATH";
+
+// JSON-RPCサーバーの設定
+// port が 0 の場合はOSが空いているポートを割り当てる（エフェメラルポート）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ServerConfig {
+    // HTTPリスナーを起動する（IPCのみで使う場合は無効にできる）
+    pub http_enabled: bool,
+    pub host: String,
+    pub port: u16,
+    // 指定された場合、port から port_range_end まで順に空いているポートを探す
+    pub port_range_end: Option<u16>,
+    // すべての候補ポートが使用中の場合にエフェメラルポートで起動する
+    pub ephemeral_fallback: bool,
+    pub allowed_origins: Vec<String>,
+    pub threads: usize,
+    // HTTPリスナーをHTTPSで待ち受ける
+    pub tls_enabled: bool,
+    // PEM形式の証明書チェーンと秘密鍵（指定しない場合はデータディレクトリの自己署名証明書を使う）
+    pub tls_cert_path: Option<String>,
+    pub tls_key_path: Option<String>,
+    // WebSocketリスナーを起動する（HTTPと同じホストで別ポート）
+    pub ws_enabled: bool,
+    pub ws_port: u16,
+    // ローカル自動化向けのIPCソケット（TCPポートを開かない）
+    pub ipc_enabled: bool,
+    // 指定しない場合はアプリのデータディレクトリの rpc.sock（Windowsでは名前付きパイプ）
+    pub ipc_path: Option<String>,
+    // 1回のバッチリクエストに含められる呼び出しの最大数
+    pub max_batch_size: usize,
+    // HTTPのリクエスト本文の最大バイト数
+    pub max_request_body_bytes: usize,
+    // HTTPでクライアント（トークン）ごとに1秒あたりに受け付けるリクエスト数（Noneの場合は制限しない）
+    pub rate_limit_per_sec: Option<u32>,
+    // 連続して受け付けるリクエスト数（Noneの場合は rate_limit_per_sec と同じ）
+    pub rate_limit_burst: Option<u32>,
+    // HTTPで同時に処理するリクエストの最大数（Noneの場合は制限しない）
+    pub max_in_flight_requests: Option<usize>,
+    // 停止時に処理中のリクエストの完了を待つ最大秒数（経過後は強制的に閉じる。0の場合は待たない）
+    pub shutdown_timeout_secs: u64,
+    // メモリに保持するアクセスログの件数
+    pub log_memory_entries: usize,
+    // アクセスログをデータディレクトリの logs/requests.jsonl に保存する
+    pub log_persist: bool,
+    // ログファイルがこのサイズを超えるか、作成からこの秒数が経過するとローテーションする
+    pub log_max_file_bytes: u64,
+    pub log_max_file_age_secs: Option<u64>,
+    // 残すローテーション済みのログファイルの数
+    pub log_retention_files: usize,
+    // 値を伏せて記録するヘッダー名（大文字小文字を区別しない）
+    pub log_redact_headers: Vec<String>,
+    // パラメータと結果のうち値を伏せるフィールド（JSON Pointer、"*" は任意の要素に一致）
+    pub log_redact_fields: Vec<String>,
+    // 本文・レスポンス・パラメータ・結果を記録せず、メタデータのみ記録する
+    pub log_metadata_only: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            http_enabled: true,
+            host: "127.0.0.1".to_string(),
+            port: 3030,
+            port_range_end: None,
+            ephemeral_fallback: false,
+            allowed_origins: vec![
+                "http://localhost:1420".to_string(), // Development UI
+                "tauri://localhost".to_string(),     // Tauri app
+                "http://localhost:3000".to_string(), // For external testing
+            ],
+            threads: 1,
+            tls_enabled: false,
+            tls_cert_path: None,
+            tls_key_path: None,
+            ws_enabled: false,
+            ws_port: 3031,
+            ipc_enabled: false,
+            ipc_path: None,
+            max_batch_size: 100,
+            max_request_body_bytes: 5 * 1024 * 1024,
+            rate_limit_per_sec: None,
+            rate_limit_burst: None,
+            max_in_flight_requests: None,
+            shutdown_timeout_secs: 10,
+            log_memory_entries: DEFAULT_LOG_MEMORY_ENTRIES,
+            log_persist: false,
+            log_max_file_bytes: 10 * 1024 * 1024,
+            log_max_file_age_secs: Some(24 * 60 * 60),
+            log_retention_files: 7,
+            log_redact_headers: DEFAULT_REDACTED_HEADERS.iter().map(|name| name.to_string()).collect(),
+            log_redact_fields: Vec::new(),
+            log_metadata_only: false,
+        }
+    }
+}
+
+impl ServerConfig {
+    // 設定ファイルを読み込み、環境変数による上書きを適用
+    // ファイルが存在しない場合はデフォルト値を使用
+    pub fn load(app_data_dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut config = Self::load_file(app_data_dir)?;
+        config.apply_env_overrides()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    // 読み込みに失敗してもエラーを出力して起動を続ける
+    // 壊れた設定ファイル・不正な値はデフォルト値、不正な環境変数は無視する
+    pub fn load_or_default(app_data_dir: &Path) -> Self {
+        let stored = Self::load_file(app_data_dir).unwrap_or_else(|e| {
+            eprintln!("Failed to read server config, using defaults: {}", e);
+            Self::default()
+        });
+
+        let config = stored.with_env_overrides_or_self();
+        match config.validate() {
+            Ok(()) => config,
+            Err(e) => {
+                eprintln!("Invalid server config, using defaults: {}", e);
+                Self::default().with_env_overrides_or_self()
+            }
+        }
+    }
+
+    // 設定ファイルに保存されている値（環境変数による上書きを含まない）
+    pub fn load_file(app_data_dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let file_path = app_data_dir.join(CONFIG_FILE_NAME);
+        if !file_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let file_content = fs::read_to_string(&file_path)?;
+        Ok(serde_json::from_str(&file_content)?)
+    }
+
+    // 環境変数による上書きを適用した設定（不正な環境変数がある場合はエラーを出力して上書きしない）
+    pub fn with_env_overrides_or_self(&self) -> Self {
+        let mut config = self.clone();
+        match config.apply_env_overrides() {
+            Ok(()) => config,
+            Err(e) => {
+                eprintln!("Ignoring invalid environment overrides: {}", e);
+                self.clone()
+            }
+        }
+    }
+
+    // edited のうち current（実行中の設定）から変更された項目だけを反映した設定
+    // 保存済みの設定に使い、環境変数で上書きされた値がファイルに書き込まれないようにする
+    pub fn with_changes(&self, current: &ServerConfig, edited: &ServerConfig) -> Result<Self, serde_json::Error> {
+        let current = serde_json::to_value(current)?;
+        let mut merged = serde_json::to_value(self)?;
+        if let (serde_json::Value::Object(edited), Some(merged)) = (serde_json::to_value(edited)?, merged.as_object_mut()) {
+            for (key, value) in edited {
+                if current.get(&key) != Some(&value) {
+                    merged.insert(key, value);
+                }
+            }
+        }
+        serde_json::from_value(merged)
+    }
+
+    // 設定ファイルに保存
+    pub fn save(&self, app_data_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if !app_data_dir.exists() {
+            fs::create_dir_all(app_data_dir)?;
+        }
+
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(app_data_dir.join(CONFIG_FILE_NAME), json)?;
+        Ok(())
+    }
+
+    // 環境変数が設定されていれば対応する値を上書き
+    pub fn apply_env_overrides(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Ok(enabled) = env::var(ENV_HTTP_ENABLED) {
+            self.http_enabled = parse_bool(ENV_HTTP_ENABLED, &enabled)?;
+        }
+
+        if let Ok(host) = env::var(ENV_HOST) {
+            self.host = host;
+        }
+
+        if let Ok(port) = env::var(ENV_PORT) {
+            self.port = port
+                .parse()
+                .map_err(|_| format!("Invalid {}: {}", ENV_PORT, port))?;
+        }
+
+        if let Ok(end) = env::var(ENV_PORT_RANGE_END) {
+            self.port_range_end = Some(
+                end.parse()
+                    .map_err(|_| format!("Invalid {}: {}", ENV_PORT_RANGE_END, end))?,
+            );
+        }
+
+        if let Ok(fallback) = env::var(ENV_EPHEMERAL_FALLBACK) {
+            self.ephemeral_fallback = parse_bool(ENV_EPHEMERAL_FALLBACK, &fallback)?;
+        }
+
+        if let Ok(origins) = env::var(ENV_ALLOWED_ORIGINS) {
+            self.allowed_origins = origins
+                .split(',')
+                .map(|origin| origin.trim().to_string())
+                .filter(|origin| !origin.is_empty())
+                .collect();
+        }
+
+        if let Ok(threads) = env::var(ENV_THREADS) {
+            self.threads = threads
+                .parse()
+                .map_err(|_| format!("Invalid {}: {}", ENV_THREADS, threads))?;
+        }
+
+        if let Ok(enabled) = env::var(ENV_TLS_ENABLED) {
+            self.tls_enabled = parse_bool(ENV_TLS_ENABLED, &enabled)?;
+        }
+
+        if let Ok(path) = env::var(ENV_TLS_CERT_PATH) {
+            self.tls_cert_path = Some(path).filter(|path| !path.is_empty());
+        }
+
+        if let Ok(path) = env::var(ENV_TLS_KEY_PATH) {
+            self.tls_key_path = Some(path).filter(|path| !path.is_empty());
+        }
+
+        if let Ok(enabled) = env::var(ENV_WS_ENABLED) {
+            self.ws_enabled = parse_bool(ENV_WS_ENABLED, &enabled)?;
+        }
+
+        if let Ok(port) = env::var(ENV_WS_PORT) {
+            self.ws_port = port
+                .parse()
+                .map_err(|_| format!("Invalid {}: {}", ENV_WS_PORT, port))?;
+        }
+
+        if let Ok(enabled) = env::var(ENV_IPC_ENABLED) {
+            self.ipc_enabled = parse_bool(ENV_IPC_ENABLED, &enabled)?;
+        }
+
+        if let Ok(path) = env::var(ENV_IPC_PATH) {
+            self.ipc_path = Some(path).filter(|path| !path.is_empty());
+        }
+
+        if let Ok(size) = env::var(ENV_MAX_BATCH_SIZE) {
+            self.max_batch_size = size
+                .parse()
+                .map_err(|_| format!("Invalid {}: {}", ENV_MAX_BATCH_SIZE, size))?;
+        }
+
+        if let Ok(size) = env::var(ENV_MAX_BODY_BYTES) {
+            self.max_request_body_bytes = size
+                .parse()
+                .map_err(|_| format!("Invalid {}: {}", ENV_MAX_BODY_BYTES, size))?;
+        }
+
+        // 空文字列の場合は制限を解除する
+        if let Ok(rate) = env::var(ENV_RATE_LIMIT_PER_SEC) {
+            self.rate_limit_per_sec = match rate.as_str() {
+                "" => None,
+                _ => Some(
+                    rate.parse()
+                        .map_err(|_| format!("Invalid {}: {}", ENV_RATE_LIMIT_PER_SEC, rate))?,
+                ),
+            };
+        }
+
+        if let Ok(max) = env::var(ENV_MAX_IN_FLIGHT) {
+            self.max_in_flight_requests = match max.as_str() {
+                "" => None,
+                _ => Some(
+                    max.parse()
+                        .map_err(|_| format!("Invalid {}: {}", ENV_MAX_IN_FLIGHT, max))?,
+                ),
+            };
+        }
+
+        if let Ok(timeout) = env::var(ENV_SHUTDOWN_TIMEOUT_SECS) {
+            self.shutdown_timeout_secs = timeout
+                .parse()
+                .map_err(|_| format!("Invalid {}: {}", ENV_SHUTDOWN_TIMEOUT_SECS, timeout))?;
+        }
+
+        Ok(())
+    }
+
+    // 設定値の検証
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.http_enabled && !self.ws_enabled && !self.ipc_enabled {
+            return Err("At least one of http_enabled, ws_enabled or ipc_enabled must be true".to_string());
+        }
+
+        if self.threads == 0 {
+            return Err("threads must be at least 1".to_string());
+        }
+
+        if self.max_batch_size == 0 {
+            return Err("max_batch_size must be at least 1".to_string());
+        }
+
+        if self.max_request_body_bytes == 0 {
+            return Err("max_request_body_bytes must be at least 1".to_string());
+        }
+
+        if self.rate_limit_per_sec == Some(0) {
+            return Err("rate_limit_per_sec must be at least 1".to_string());
+        }
+
+        if self.rate_limit_burst == Some(0) {
+            return Err("rate_limit_burst must be at least 1".to_string());
+        }
+
+        if self.max_in_flight_requests == Some(0) {
+            return Err("max_in_flight_requests must be at least 1".to_string());
+        }
+
+        if self.log_memory_entries == 0 {
+            return Err("log_memory_entries must be at least 1".to_string());
+        }
+
+        if self.log_max_file_bytes == 0 {
+            return Err("log_max_file_bytes must be at least 1".to_string());
+        }
+
+        if self.log_max_file_age_secs == Some(0) {
+            return Err("log_max_file_age_secs must be at least 1".to_string());
+        }
+
+        if self.tls_cert_path.is_some() != self.tls_key_path.is_some() {
+            return Err("tls_cert_path and tls_key_path must be specified together".to_string());
+        }
+
+        if let Some(pointer) = self
+            .log_redact_fields
+            .iter()
+            .find(|pointer| !pointer.is_empty() && !pointer.starts_with('/'))
+        {
+            return Err(format!("log_redact_fields entry \"{}\" must be a JSON Pointer starting with '/'", pointer));
+        }
+
+        if let Some(end) = self.port_range_end {
+            if self.port == 0 {
+                return Err("port_range_end cannot be used with an ephemeral port (0)".to_string());
+            }
+            if end < self.port {
+                return Err(format!("port_range_end ({}) must not be lower than port ({})", end, self.port));
+            }
+        }
+
+        if self.ws_enabled && self.ws_port != 0 && self.ws_port == self.port {
+            return Err(format!("ws_port ({}) must differ from port", self.ws_port));
+        }
+
+        self.socket_addr()?;
+
+        Ok(())
+    }
+
+    // 環境変数でポートが指定されているか
+    pub fn port_overridden_by_env() -> bool {
+        env::var(ENV_PORT).is_ok()
+    }
+
+    // バインドするアドレス
+    pub fn socket_addr(&self) -> Result<SocketAddr, String> {
+        format!("{}:{}", self.host, self.port)
+            .parse::<SocketAddr>()
+            .or_else(|_| format!("[{}]:{}", self.host, self.port).parse::<SocketAddr>())
+            .map_err(|_| format!("Invalid bind address: {}:{}", self.host, self.port))
+    }
+
+    // 起動時に順に試すポートの一覧
+    pub fn candidate_ports(&self) -> Vec<u16> {
+        let reserved = Some(self.ws_port).filter(|port| self.ws_enabled && *port != 0);
+        self.candidate_ports_from(self.port, reserved)
+    }
+
+    // WebSocketリスナーで順に試すポートの一覧（HTTPと同じ幅の範囲を使う）
+    pub fn ws_candidate_ports(&self) -> Vec<u16> {
+        let reserved = Some(self.port).filter(|port| self.http_enabled && *port != 0);
+        self.candidate_ports_from(self.ws_port, reserved)
+    }
+
+    // 範囲内にもう一方のリスナーのポート（reserved）があれば除く
+    fn candidate_ports_from(&self, base: u16, reserved: Option<u16>) -> Vec<u16> {
+        let mut ports: Vec<u16> = match self.port_range_end {
+            Some(end) if end > self.port && base != 0 => {
+                let last = base.saturating_add(end - self.port);
+                (base..=last).filter(|port| Some(*port) != reserved).collect()
+            }
+            _ => vec![base],
+        };
+
+        if self.ephemeral_fallback && !ports.contains(&0) {
+            ports.push(0);
+        }
+
+        ports
+    }
+
+    // 指定したポートでバインドするアドレス
+    pub fn socket_addr_with_port(&self, port: u16) -> Result<SocketAddr, String> {
+        let mut addr = self.socket_addr()?;
+        addr.set_port(port);
+        Ok(addr)
+    }
+
+    // IPCソケットのパス
+    pub fn ipc_endpoint(&self, app_data_dir: &Path) -> String {
+        if let Some(path) = &self.ipc_path {
+            return path.clone();
+        }
+
+        #[cfg(unix)]
+        {
+            app_data_dir.join(IPC_SOCKET_NAME).to_string_lossy().into_owned()
+        }
+
+        #[cfg(windows)]
+        {
+            let _ = app_data_dir;
+            IPC_PIPE_NAME.to_string()
+        }
+    }
+
+    // HTTPリスナーのURLスキーム
+    pub fn scheme(&self) -> &'static str {
+        if self.tls_enabled {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    // クライアントが接続するためのURL
+    pub fn url(&self) -> String {
+        match self.socket_addr() {
+            Ok(addr) => format!("{}://{}", self.scheme(), addr),
+            Err(_) => format!("{}://{}:{}", self.scheme(), self.host, self.port),
+        }
+    }
+}
+
+// 環境変数の真偽値（大文字・小文字を区別しない。どちらにも当てはまらない値はエラー）
+fn parse_bool(name: &str, value: &str) -> Result<bool, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("Invalid {}: {}", name, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        let config = ServerConfig::default();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.url(), "http://127.0.0.1:3030");
+    }
+
+    #[test]
+    fn test_ipv6_host_url() {
+        let config = ServerConfig {
+            host: "::1".to_string(),
+            ..ServerConfig::default()
+        };
+        assert_eq!(config.url(), "http://[::1]:3030");
+    }
+
+    #[test]
+    fn test_tls_config() {
+        let config = ServerConfig {
+            tls_enabled: true,
+            ..ServerConfig::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.url(), "https://127.0.0.1:3030");
+
+        // 証明書と秘密鍵は片方だけ指定できない
+        let config = ServerConfig {
+            tls_cert_path: Some("cert.pem".to_string()),
+            ..config
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_only_edited_values_are_merged() {
+        // 実行中の設定は環境変数でホストが上書きされている
+        let stored = ServerConfig::default();
+        let current = ServerConfig {
+            host: "0.0.0.0".to_string(),
+            ..stored.clone()
+        };
+        let edited = ServerConfig {
+            port: 4000,
+            ..current.clone()
+        };
+
+        let merged = stored.with_changes(&current, &edited).unwrap();
+        assert_eq!(merged.port, 4000);
+        assert_eq!(merged.host, stored.host);
+    }
+
+    #[test]
+    fn test_candidate_ports() {
+        let config = ServerConfig {
+            port: 3030,
+            port_range_end: Some(3032),
+            ephemeral_fallback: true,
+            ..ServerConfig::default()
+        };
+        assert_eq!(config.candidate_ports(), vec![3030, 3031, 3032, 0]);
+        assert_eq!(config.ws_candidate_ports(), vec![3031, 3032, 3033, 0]);
+
+        // WebSocketが有効な場合はお互いのポートを範囲から除く
+        let config = ServerConfig {
+            ws_enabled: true,
+            ..config
+        };
+        assert_eq!(config.candidate_ports(), vec![3030, 3032, 0]);
+        assert_eq!(config.ws_candidate_ports(), vec![3031, 3032, 3033, 0]);
+        let config = ServerConfig {
+            port: 3031,
+            port_range_end: Some(3033),
+            ws_port: 3030,
+            ..config
+        };
+        assert_eq!(config.ws_candidate_ports(), vec![3030, 3032, 0]);
+
+        let config = ServerConfig {
+            port: 3030,
+            port_range_end: Some(3020),
+            ..ServerConfig::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_bool() {
+        for value in ["1", "true", "TRUE", "Yes", "on"] {
+            assert_eq!(parse_bool("TEST", value), Ok(true), "{}", value);
+        }
+        for value in ["0", "false", "False", "NO", "off"] {
+            assert_eq!(parse_bool("TEST", value), Ok(false), "{}", value);
+        }
+        assert_eq!(parse_bool("TEST", "ture"), Err("Invalid TEST: ture".to_string()));
+        assert!(parse_bool("TEST", "").is_err());
+    }
+
+    #[test]
+    fn test_at_least_one_transport_is_required() {
+        let config = ServerConfig {
+            http_enabled: false,
+            ws_enabled: false,
+            ipc_enabled: true,
+            ..ServerConfig::default()
+        };
+        assert!(config.validate().is_ok());
+
+        let config = ServerConfig {
+            ipc_enabled: false,
+            ..config
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_zero_threads_is_invalid() {
+        let config = ServerConfig {
+            threads: 0,
+            ..ServerConfig::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_zero_max_batch_size_is_invalid() {
+        let config = ServerConfig {
+            max_batch_size: 0,
+            ..ServerConfig::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_zero_limits_are_invalid() {
+        let config = ServerConfig {
+            rate_limit_per_sec: Some(0),
+            ..ServerConfig::default()
+        };
+        assert!(config.validate().is_err());
+
+        let config = ServerConfig {
+            max_in_flight_requests: Some(0),
+            ..ServerConfig::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
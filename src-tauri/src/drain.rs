@@ -0,0 +1,96 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+// サーバーの停止時に処理中のリクエストの完了を待つための状態（起動ごとに作り直す）
+#[derive(Default)]
+pub struct Drain {
+    state: Mutex<DrainState>,
+    idle: Condvar,
+}
+
+#[derive(Default)]
+struct DrainState {
+    draining: bool,
+    active: usize,
+}
+
+impl Drain {
+    // 処理中のリクエストとして数える（停止処理が始まっている場合はNone）
+    pub fn enter(self: &Arc<Self>) -> Option<DrainGuard> {
+        let mut state = self.state.lock().unwrap();
+        if state.draining {
+            return None;
+        }
+        state.active += 1;
+        Some(DrainGuard(self.clone()))
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.state.lock().unwrap().draining
+    }
+
+    // 処理中のリクエストの数
+    pub fn active(&self) -> usize {
+        self.state.lock().unwrap().active
+    }
+
+    // 新しいリクエストの受け付けを止める（drain を呼ぶ前に止めておく場合）
+    pub fn close(&self) {
+        self.state.lock().unwrap().draining = true;
+    }
+
+    // 新しいリクエストの受け付けを止め、処理中のリクエストが終わるまで最大 timeout 待つ
+    // 時間内に終わらなかったリクエストの数を返す
+    pub fn drain(&self, timeout: Duration) -> usize {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.state.lock().unwrap();
+        state.draining = true;
+
+        while state.active > 0 {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            state = self.idle.wait_timeout(state, remaining).unwrap().0;
+        }
+        state.active
+    }
+}
+
+// 処理中のリクエストのガード（破棄すると処理中から外れる）
+pub struct DrainGuard(Arc<Drain>);
+
+impl Drop for DrainGuard {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap();
+        state.active -= 1;
+        if state.active == 0 {
+            self.0.idle.notify_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drain_waits_for_active_requests() {
+        let drain = Arc::new(Drain::default());
+        let guard = drain.enter().unwrap();
+        let _other = drain.enter().unwrap();
+        assert_eq!(drain.active(), 2);
+
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            drop(guard);
+        });
+        // 1件は時間内に終わり、もう1件は残る
+        assert_eq!(drain.drain(Duration::from_millis(200)), 1);
+        handle.join().unwrap();
+
+        // 停止処理が始まった後は受け付けない
+        assert!(drain.is_draining());
+        assert!(drain.enter().is_none());
+    }
+}
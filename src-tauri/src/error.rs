@@ -0,0 +1,228 @@
+use jsonrpc_core::{Error, ErrorCode};
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
+use std::fmt;
+
+// アプリケーションエラーのJSON-RPCエラーコード（クライアントが分岐に使うため変更しないこと）
+// トークンのスコープ外のメソッドを呼び出した場合
+pub const ERROR_CODE_FORBIDDEN: i64 = -32001;
+pub const ERROR_CODE_NOT_FOUND: i64 = -32002;
+pub const ERROR_CODE_VALIDATION: i64 = -32003;
+pub const ERROR_CODE_STORAGE: i64 = -32004;
+pub const ERROR_CODE_CONFLICT: i64 = -32005;
+pub const ERROR_CODE_UNAVAILABLE: i64 = -32006;
+pub const ERROR_CODE_RATE_LIMITED: i64 = -32007;
+pub const ERROR_CODE_INTERNAL: i64 = -32603;
+
+// エラーコードの説明（OpenRPCドキュメントで使用）
+pub fn error_description(code: i64) -> &'static str {
+    match code {
+        -32602 => "Invalid params",
+        ERROR_CODE_FORBIDDEN => "Method is not allowed for this token",
+        ERROR_CODE_NOT_FOUND => "Resource not found",
+        ERROR_CODE_VALIDATION => "Validation failed",
+        ERROR_CODE_STORAGE => "Storage I/O failed",
+        ERROR_CODE_CONFLICT => "Revision conflict",
+        ERROR_CODE_UNAVAILABLE => "Service unavailable",
+        ERROR_CODE_RATE_LIMITED => "Too many requests",
+        ERROR_CODE_INTERNAL => "Internal error",
+        _ => "Unknown error",
+    }
+}
+
+// RPCメソッドとTauriコマンドで共通のエラー
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppError {
+    // 指定したリソースが存在しない
+    NotFound { resource: String, id: String },
+    // 入力値が不正
+    Validation { field: Option<String>, message: String },
+    // ファイルの読み書きに失敗
+    Storage(String),
+    // 更新対象がすでに変更されている
+    Conflict { id: String, expected: u64, actual: u64 },
+    // 必要な機能が初期化されていない
+    Unavailable(String),
+    // リクエスト数の制限を超えた（limit は "rate" または "in_flight"）
+    RateLimited { limit: String, retry_after_secs: u64 },
+    // その他の内部エラー
+    Internal(String),
+}
+
+impl AppError {
+    pub fn not_found(resource: &str, id: &str) -> Self {
+        AppError::NotFound {
+            resource: resource.to_string(),
+            id: id.to_string(),
+        }
+    }
+
+    pub fn validation(field: Option<&str>, message: impl fmt::Display) -> Self {
+        AppError::Validation {
+            field: field.map(str::to_string),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn storage(error: impl fmt::Display) -> Self {
+        AppError::Storage(error.to_string())
+    }
+
+    pub fn internal(error: impl fmt::Display) -> Self {
+        AppError::Internal(error.to_string())
+    }
+
+    // クライアントが分岐に使うエラーの種類
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::NotFound { .. } => "not_found",
+            AppError::Validation { .. } => "validation",
+            AppError::Storage(_) => "storage",
+            AppError::Conflict { .. } => "conflict",
+            AppError::Unavailable(_) => "unavailable",
+            AppError::RateLimited { .. } => "rate_limited",
+            AppError::Internal(_) => "internal",
+        }
+    }
+
+    pub fn code(&self) -> i64 {
+        match self {
+            AppError::NotFound { .. } => ERROR_CODE_NOT_FOUND,
+            AppError::Validation { .. } => ERROR_CODE_VALIDATION,
+            AppError::Storage(_) => ERROR_CODE_STORAGE,
+            AppError::Conflict { .. } => ERROR_CODE_CONFLICT,
+            AppError::Unavailable(_) => ERROR_CODE_UNAVAILABLE,
+            AppError::RateLimited { .. } => ERROR_CODE_RATE_LIMITED,
+            AppError::Internal(_) => ERROR_CODE_INTERNAL,
+        }
+    }
+
+    // エラーの詳細（常に kind を含む）
+    pub fn data(&self) -> Value {
+        match self {
+            AppError::NotFound { resource, id } => json!({ "kind": self.kind(), "resource": resource, "id": id }),
+            AppError::Validation { field, .. } => json!({ "kind": self.kind(), "field": field }),
+            AppError::Conflict { id, expected, actual } => json!({
+                "kind": self.kind(),
+                "id": id,
+                "expected_revision": expected,
+                "actual_revision": actual,
+            }),
+            AppError::RateLimited { limit, retry_after_secs } => json!({
+                "kind": self.kind(),
+                "limit": limit,
+                "retry_after_secs": retry_after_secs,
+            }),
+            AppError::Storage(_) | AppError::Unavailable(_) | AppError::Internal(_) => json!({ "kind": self.kind() }),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound { resource, id } => write!(f, "{} not found: {}", resource, id),
+            AppError::Validation { message, .. } => write!(f, "{}", message),
+            AppError::Storage(message) => write!(f, "Storage error: {}", message),
+            AppError::Conflict { id, expected, actual } => write!(
+                f,
+                "Conflict on {}: expected revision {}, but current revision is {}",
+                id, expected, actual
+            ),
+            AppError::Unavailable(message) => write!(f, "{}", message),
+            AppError::RateLimited { retry_after_secs, .. } => {
+                write!(f, "Too many requests; retry after {} seconds", retry_after_secs)
+            }
+            AppError::Internal(message) => write!(f, "Internal error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<std::io::Error> for AppError {
+    fn from(error: std::io::Error) -> Self {
+        AppError::storage(error)
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(error: serde_json::Error) -> Self {
+        AppError::storage(error)
+    }
+}
+
+// サーバーの起動などの失敗（分類済みの AppError はそのまま、それ以外は内部エラー）
+impl From<Box<dyn std::error::Error>> for AppError {
+    fn from(error: Box<dyn std::error::Error>) -> Self {
+        match error.downcast::<AppError>() {
+            Ok(error) => *error,
+            Err(error) => AppError::internal(error),
+        }
+    }
+}
+
+// JSON-RPCのエラーレスポンスに変換
+impl From<AppError> for Error {
+    fn from(error: AppError) -> Self {
+        let code = match &error {
+            AppError::Internal(_) => ErrorCode::InternalError,
+            _ => ErrorCode::ServerError(error.code()),
+        };
+
+        Error {
+            code,
+            message: error.to_string(),
+            data: Some(error.data()),
+        }
+    }
+}
+
+// Tauriコマンドのエラーとしてフロントエンドに渡す形式
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 4)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("code", &self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("data", &self.data())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rpc_error_has_stable_code_and_data() {
+        let error: Error = AppError::not_found("note", "abc").into();
+        assert_eq!(error.code, ErrorCode::ServerError(ERROR_CODE_NOT_FOUND));
+        assert_eq!(
+            error.data,
+            Some(json!({ "kind": "not_found", "resource": "note", "id": "abc" }))
+        );
+
+        let error: Error = AppError::internal("lock poisoned").into();
+        assert_eq!(error.code, ErrorCode::InternalError);
+    }
+
+    #[test]
+    fn test_serialize_for_tauri() {
+        let value = serde_json::to_value(AppError::validation(Some("title"), "Title must not be empty")).unwrap();
+        assert_eq!(value["kind"], "validation");
+        assert_eq!(value["code"], ERROR_CODE_VALIDATION);
+        assert_eq!(value["message"], "Title must not be empty");
+        assert_eq!(value["data"]["field"], "title");
+    }
+
+    #[test]
+    fn test_boxed_error_keeps_kind() {
+        let error: Box<dyn std::error::Error> = AppError::Unavailable("no available port".to_string()).into();
+        assert_eq!(AppError::from(error).kind(), "unavailable");
+
+        let error: Box<dyn std::error::Error> = "unexpected".into();
+        assert_eq!(AppError::from(error), AppError::internal("unexpected"));
+    }
+}
@@ -0,0 +1,200 @@
+use jsonrpc_http_server::hyper::StatusCode;
+use serde_json::{json, Value};
+
+use crate::logs::LogEntry;
+
+// ブラウザの開発者ツールが読み込めるHARのバージョン
+const HAR_VERSION: &str = "1.2";
+
+// ログエントリをHAR 1.2のドキュメントに変換（古い順に並べる）
+// HTTPステータスのないWebSocket・IPCのメッセージは status 0 になる
+pub fn document(entries: &[LogEntry]) -> Value {
+    json!({
+        "log": {
+            "version": HAR_VERSION,
+            "creator": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "entries": entries.iter().map(har_entry).collect::<Vec<_>>(),
+        }
+    })
+}
+
+fn har_entry(entry: &LogEntry) -> Value {
+    let time = entry.elapsed_ms.unwrap_or(0);
+    let status = entry.status.unwrap_or(0);
+    let status_text = StatusCode::from_u16(status)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or("");
+
+    let mut request = json!({
+        "method": entry.method,
+        "url": url(entry),
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": entry.headers,
+        "queryString": query_string(&entry.uri),
+        "headersSize": -1,
+        "bodySize": entry.body_size.unwrap_or(0),
+    });
+    if let Some(body) = &entry.body {
+        request["postData"] = json!({
+            "mimeType": header(entry, "content-type").unwrap_or("application/json"),
+            "text": body,
+        });
+    }
+
+    // レスポンスヘッダーは記録していない
+    let response = json!({
+        "status": status,
+        "statusText": status_text,
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": [],
+        "content": {
+            "size": entry.response_size.unwrap_or(0),
+            "mimeType": mime_type(entry.response.as_deref()),
+            "text": entry.response.clone().unwrap_or_default(),
+        },
+        "redirectURL": "",
+        "headersSize": -1,
+        "bodySize": entry.response_size.unwrap_or(0),
+    });
+
+    // HARにない項目は "_" で始まるカスタムフィールドに入れる
+    json!({
+        "startedDateTime": iso8601(entry.timestamp),
+        "time": time,
+        "request": request,
+        "response": response,
+        "cache": {},
+        "timings": { "send": 0, "wait": time, "receive": 0 },
+        "_requestId": entry.request_id,
+        "_clientRequestId": entry.client_request_id,
+        "_rpcId": entry.rpc_id,
+        "_rpcMethod": entry.rpc_method,
+        "_tokenId": entry.token_id,
+        "_bodyTruncated": entry.body_truncated,
+        "_responseTruncated": entry.response_truncated,
+        "_batchItems": entry.items,
+        "_replayOf": entry.replay_of,
+    })
+}
+
+fn header<'a>(entry: &'a LogEntry, name: &str) -> Option<&'a str> {
+    entry
+        .headers
+        .iter()
+        .find(|header| header.name.eq_ignore_ascii_case(name))
+        .map(|header| header.value.as_str())
+}
+
+// ログエントリのURL（HTTPは記録したスキーム、IPCはソケットのパスを ipc:// で表す）
+fn url(entry: &LogEntry) -> String {
+    let host = header(entry, "host").unwrap_or("localhost");
+    match entry.method.as_str() {
+        "IPC" => format!("ipc://{}", entry.uri),
+        "WS" => format!("ws://{}{}", host, entry.uri),
+        _ => format!("{}://{}{}", entry.scheme.as_deref().unwrap_or("http"), host, entry.uri),
+    }
+}
+
+fn query_string(uri: &str) -> Vec<Value> {
+    let Some((_, query)) = uri.split_once('?') else {
+        return Vec::new();
+    };
+
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            json!({ "name": name, "value": value })
+        })
+        .collect()
+}
+
+fn mime_type(response: Option<&str>) -> &'static str {
+    match response.map(str::trim_start) {
+        Some(text) if text.starts_with('{') || text.starts_with('[') => "application/json",
+        Some(text) if !text.is_empty() => "text/plain",
+        _ => "",
+    }
+}
+
+// UNIX時刻（秒）をISO 8601形式（UTC）に変換
+fn iso8601(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let seconds = timestamp % 86_400;
+
+    // 1970-01-01からの日数をグレゴリオ暦の年月日に変換
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.000Z",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logs::LogHeader;
+
+    #[test]
+    fn test_iso8601() {
+        assert_eq!(iso8601(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(iso8601(1_700_000_000), "2023-11-14T22:13:20.000Z");
+        assert_eq!(iso8601(951_782_400), "2000-02-29T00:00:00.000Z");
+    }
+
+    #[test]
+    fn test_har_entry() {
+        let entry = LogEntry::new(
+            "req-1".to_string(),
+            "POST".to_string(),
+            "/?debug=1".to_string(),
+            vec![LogHeader::new("host", "127.0.0.1:3030")],
+            None,
+        )
+        .with_body(r#"{"jsonrpc":"2.0","method":"echo","params":["a"],"id":1}"#)
+        .with_response(r#"{"jsonrpc":"2.0","result":"a","id":1}"#)
+        .with_status(200);
+
+        let har = document(&[entry]);
+        let entry = &har["log"]["entries"][0];
+        assert_eq!(har["log"]["version"], "1.2");
+        assert_eq!(entry["request"]["url"], "http://127.0.0.1:3030/?debug=1");
+        assert_eq!(entry["request"]["queryString"][0]["name"], "debug");
+        assert_eq!(entry["request"]["headers"][0]["value"], "127.0.0.1:3030");
+        assert_eq!(entry["response"]["statusText"], "OK");
+        assert_eq!(entry["response"]["content"]["mimeType"], "application/json");
+        assert_eq!(entry["_requestId"], "req-1");
+
+        // HTTPSで受け付けたリクエストは https:// のURLになる
+        let entry = LogEntry::new(
+            "req-2".to_string(),
+            "POST".to_string(),
+            "/".to_string(),
+            vec![LogHeader::new("host", "localhost:3030")],
+            None,
+        )
+        .with_scheme("https");
+        assert_eq!(document(&[entry])["log"]["entries"][0]["request"]["url"], "https://localhost:3030/");
+    }
+}
@@ -0,0 +1,185 @@
+use std::env;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+use crate::auth::TokenAuth;
+use crate::config::ServerConfig;
+use crate::logs::LogEntry;
+use crate::notes::NoteStore;
+use crate::server::JsonRpcServer;
+use crate::settings::LaunchSettings;
+
+// ヘッドレスモードで使うデータディレクトリを指定する環境変数（--data-dir が優先）
+const ENV_DATA_DIR: &str = "RPC_SERVER_DATA_DIR";
+
+// tauri.conf.json の identifier と同じ（デフォルトではアプリと同じデータディレクトリを使う）
+const APP_IDENTIFIER: &str = "com.app-with-rpc.app";
+
+// コマンドライン引数で指定するヘッドレスモードの設定
+#[derive(Debug, Default, PartialEq)]
+pub struct HeadlessOptions {
+    pub data_dir: Option<PathBuf>,
+}
+
+impl HeadlessOptions {
+    // --headless が指定されていない場合はNone（通常どおりウィンドウを開く。ほかの引数は検証しない）
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Option<Self>, String> {
+        let mut headless = false;
+        let mut options = Self::default();
+        let mut error = None;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => headless = true,
+                "--data-dir" => match args.next() {
+                    Some(dir) => options.data_dir = Some(PathBuf::from(dir)),
+                    None => error = Some("--data-dir requires a path".to_string()),
+                },
+                _ => {
+                    if let Some(dir) = arg.strip_prefix("--data-dir=") {
+                        options.data_dir = Some(PathBuf::from(dir));
+                    } else if arg.starts_with("--") {
+                        error.get_or_insert_with(|| format!("Unknown option: {}", arg));
+                    }
+                }
+            }
+        }
+
+        if !headless {
+            return Ok(None);
+        }
+        match error {
+            Some(error) => Err(error),
+            None => Ok(Some(options)),
+        }
+    }
+
+    fn data_dir(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        if let Some(dir) = &self.data_dir {
+            return Ok(dir.clone());
+        }
+        if let Some(dir) = env::var_os(ENV_DATA_DIR).filter(|dir| !dir.is_empty()) {
+            return Ok(PathBuf::from(dir));
+        }
+        default_data_dir().ok_or_else(|| format!("Could not determine the data directory (use --data-dir or {})", ENV_DATA_DIR).into())
+    }
+}
+
+// Tauriの app_data_dir と同じ場所（ウィンドウシステムに依存しないよう自前で解決する）
+fn default_data_dir() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+    base.map(|dir| dir.join(APP_IDENTIFIER))
+}
+
+// 標準出力に書き出すアクセスログの1行
+fn format_log_line(entry: &LogEntry) -> String {
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+    format!(
+        "{} {} {} {} {} {}",
+        entry.request_id,
+        entry.method,
+        entry.uri,
+        optional(entry.rpc_method.clone()),
+        optional(entry.status.map(|status| status.to_string())),
+        optional(entry.elapsed_ms.map(|ms| format!("{}ms", ms))),
+    )
+}
+
+// ウィンドウを作らずにサーバーを起動し、SIGINT / SIGTERM を受け取るまで待つ
+// Tauriのランタイムを使わないため、ディスプレイのない環境でも動く
+pub fn run(options: HeadlessOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let data_dir = options.data_dir()?;
+    let note_store = NoteStore::open(&data_dir)
+        .map_err(|e| format!("Failed to initialize note store in {}: {}", data_dir.display(), e))?;
+    let config = ServerConfig::load(&data_dir)?;
+    let auth = TokenAuth::load(&data_dir)?;
+    let launch = LaunchSettings::load(&data_dir)?;
+    println!("Data directory: {}", data_dir.display());
+
+    let mut server = JsonRpcServer::new();
+    server.set_app_data_dir(data_dir);
+    server.set_note_store(Arc::new(note_store));
+    server.set_config(config);
+    server.set_auth(auth);
+    server.set_preferred_port(launch.preferred_port());
+
+    // 完了したリクエストだけを記録した順に出力する（UI向けの通知は開始時にも送られ、間引かれることもあるため使わない）
+    let completed_logs = server.subscribe_completed_logs();
+    let log_printer = thread::spawn(move || {
+        for entry in completed_logs {
+            println!("{}", format_log_line(&entry));
+        }
+    });
+
+    // 2回目のシグナルでは処理中のリクエストを待たずに終了する
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let shutdown_requested = AtomicBool::new(false);
+    ctrlc::set_handler(move || {
+        if shutdown_requested.swap(true, Ordering::SeqCst) {
+            eprintln!("Received second signal, exiting immediately");
+            std::process::exit(130);
+        }
+        let _ = shutdown_tx.send(());
+    })?;
+
+    server.start()?;
+    if server.config().http_enabled {
+        println!("JSON-RPC server listening on {}", server.url());
+    }
+    if let Some(ws_url) = server.ws_url() {
+        println!("WebSocket server listening on {}", ws_url);
+    }
+    if let Some(ipc_path) = server.ipc_path() {
+        println!("IPC socket listening on {}", ipc_path);
+    }
+    if let Some(fingerprint) = server.tls_fingerprint()? {
+        println!("TLS certificate (SHA-256): {}", fingerprint);
+    }
+
+    shutdown_rx.recv()?;
+    println!("Shutting down...");
+    server.stop()?;
+    // サーバーを破棄してチャネルを閉じ、残りのログを出力し終えてから終了する
+    drop(server);
+    let _ = log_printer.join();
+    println!("Server stopped");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<HeadlessOptions>, String> {
+        HeadlessOptions::from_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_headless_args() {
+        assert_eq!(parse(&[]).unwrap(), None);
+        assert_eq!(parse(&["--data-dir", "/tmp/notes"]).unwrap(), None);
+        assert_eq!(parse(&["--unknown"]).unwrap(), None);
+        assert_eq!(parse(&["--headless"]).unwrap(), Some(HeadlessOptions::default()));
+
+        let expected = Some(HeadlessOptions {
+            data_dir: Some(PathBuf::from("/tmp/notes")),
+        });
+        assert_eq!(parse(&["--headless", "--data-dir", "/tmp/notes"]).unwrap(), expected);
+        assert_eq!(parse(&["--data-dir=/tmp/notes", "--headless"]).unwrap(), expected);
+
+        assert!(parse(&["--headless", "--data-dir"]).is_err());
+        assert!(parse(&["--headless", "--port"]).is_err());
+    }
+}
@@ -1,8 +1,67 @@
+// Error module
+mod error;
+use error::AppError;
+
 // Server module
 mod server;
-use server::{JsonRpcServer, LogEntry};
+use server::JsonRpcServer;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
-use tauri::{State, Manager};
+use tauri::{AppHandle, State, Manager};
+
+// RPC method table module
+mod rpc;
+
+// Typed RPC params module
+mod params;
+
+// OpenRPC document module
+mod openrpc;
+
+// Config module
+mod config;
+use config::ServerConfig;
+
+// Secret file module
+mod secret_file;
+
+// Auth module
+mod auth;
+use auth::{ApiToken, ApiTokenInfo, AuthStatus, TokenAuth};
+
+// Request log module
+mod logs;
+use logs::{export_entries, LogEntry, LogExportFormat, LogOrder, LogPage, LogQuery, LogSource};
+
+// HAR export module
+mod har;
+
+// Log redaction module
+mod redact;
+
+// Log replay module
+mod replay;
+use replay::ReplayResult;
+
+// Metrics module
+mod metrics;
+
+// Request limits module
+mod limits;
+
+// TLS module
+mod tls;
+
+// Graceful shutdown module
+mod drain;
+
+// Launch settings module
+mod settings;
+use settings::{LaunchSettings, ServerSettings};
+
+// Headless mode module
+mod headless;
+use headless::HeadlessOptions;
 
 // Notes module
 mod notes;
@@ -11,20 +70,66 @@ use notes::{Note, NoteStore};
 // Server state managed by Tauri
 struct ServerState(Arc<Mutex<JsonRpcServer>>);
 
+// サーバーの起動・停止・設定の変更を1つずつ実行するためのロック
+// 停止処理で待つ間はサーバーのロックを手放すため、一連の操作の途中に別の操作が割り込まないようにする
+#[derive(Default)]
+struct ServerLifecycle(Mutex<()>);
+
 // Notes state managed by Tauri
 struct NotesState(Arc<NoteStore>);
 
 // Tauri commands
-#[tauri::command]
-fn start_server(state: State<ServerState>) -> Result<(), String> {
+// HTTPSの場合は証明書の生成も行うため、メインスレッドをブロックしないように別スレッドで実行する
+#[tauri::command(async)]
+fn start_server(state: State<ServerState>, lifecycle: State<ServerLifecycle>) -> Result<(), AppError> {
+    let _lifecycle = lifecycle.0.lock().unwrap();
     let mut server = state.0.lock().unwrap();
-    server.start().map_err(|e| e.to_string())
+    server.start().map_err(AppError::from)
 }
 
-#[tauri::command]
-fn stop_server(state: State<ServerState>) -> Result<(), String> {
+// 処理中のリクエストの完了を待つため、メインスレッドをブロックしないように別スレッドで実行する
+#[tauri::command(async)]
+fn stop_server(state: State<ServerState>, lifecycle: State<ServerLifecycle>) -> Result<(), AppError> {
+    let _lifecycle = lifecycle.0.lock().unwrap();
+    if !stop_unlocked(&state)? {
+        return Err(AppError::Unavailable("Server is not running".to_string()));
+    }
+    Ok(())
+}
+
+// 停止（処理中のリクエストの完了を待つ）してから起動する。停止中の場合はそのまま起動する
+#[tauri::command(async)]
+fn restart_server(state: State<ServerState>, lifecycle: State<ServerLifecycle>) -> Result<(), AppError> {
+    let _lifecycle = lifecycle.0.lock().unwrap();
+    stop_unlocked(&state)?;
     let mut server = state.0.lock().unwrap();
-    server.stop().map_err(|e| e.to_string())
+    server.start().map_err(AppError::from)
+}
+
+// 起動中であれば停止する（停止した場合は true）
+// 処理中のリクエストを待つ間はサーバーのロックを保持しない（状態を取得するコマンドが待たされないようにする）
+fn stop_unlocked(state: &ServerState) -> Result<bool, AppError> {
+    let pending = {
+        let mut server = state.0.lock().unwrap();
+        if !server.is_running() {
+            return Ok(false);
+        }
+        server.begin_stop().map_err(AppError::from)?
+    };
+    let unfinished_requests = pending.wait();
+    state.0.lock().unwrap().finish_stop(unfinished_requests);
+    Ok(true)
+}
+
+// 設定を反映する（起動中の場合は stop_unlocked で停止してから起動し直す）
+fn apply_config(state: &ServerState, config: ServerConfig) -> Result<(), AppError> {
+    let was_running = stop_unlocked(state)?;
+    let mut server = state.0.lock().unwrap();
+    server.update_config(config).map_err(AppError::from)?;
+    if was_running && !server.is_running() {
+        server.start().map_err(AppError::from)?;
+    }
+    Ok(())
 }
 
 #[tauri::command]
@@ -34,8 +139,246 @@ fn is_server_running(state: State<ServerState>) -> bool {
 }
 
 #[tauri::command]
-fn get_server_url() -> String {
-    "http://127.0.0.1:3030".to_string()
+fn get_server_url(state: State<ServerState>) -> String {
+    let server = state.0.lock().unwrap();
+    server.url()
+}
+
+// WebSocketリスナーのURL（無効な場合はnull）
+#[tauri::command]
+fn get_ws_url(state: State<ServerState>) -> Option<String> {
+    let server = state.0.lock().unwrap();
+    server.ws_url()
+}
+
+// IPCソケットのパス（起動していない場合はnull）
+#[tauri::command]
+fn get_ipc_path(state: State<ServerState>) -> Option<String> {
+    let server = state.0.lock().unwrap();
+    server.ipc_path()
+}
+
+// HTTPSの証明書のSHA-256フィンガープリント（HTTPSが無効な場合はnull）
+#[tauri::command]
+fn get_tls_fingerprint(state: State<ServerState>) -> Result<Option<String>, AppError> {
+    let server = state.0.lock().unwrap();
+    server.tls_fingerprint().map_err(AppError::internal)
+}
+
+// サーバー設定の取得
+#[tauri::command]
+fn get_server_config(state: State<ServerState>) -> ServerConfig {
+    let server = state.0.lock().unwrap();
+    server.config().clone()
+}
+
+// サーバー設定の変更（起動中の場合は再起動される）
+// ファイルには変更された項目だけを保存し、環境変数による上書きは実行中の設定にのみ適用する
+#[tauri::command(async)]
+fn set_server_config(
+    app_handle: AppHandle,
+    state: State<ServerState>,
+    lifecycle: State<ServerLifecycle>,
+    config: ServerConfig,
+) -> Result<ServerConfig, AppError> {
+    config.validate().map_err(|e| AppError::validation(None, e))?;
+    let _lifecycle = lifecycle.0.lock().unwrap();
+
+    let app_data_dir = app_handle.path().app_data_dir().map_err(AppError::internal)?;
+    let (stored, config) = stored_config(&app_data_dir, state.0.lock().unwrap().config(), &config)?;
+    stored.save(&app_data_dir).map_err(AppError::storage)?;
+
+    apply_config(&state, config)?;
+    Ok(state.0.lock().unwrap().config().clone())
+}
+
+// 編集された設定から、ファイルに保存する設定と環境変数を適用した実行中の設定を作る
+fn stored_config(app_data_dir: &Path, current: &ServerConfig, edited: &ServerConfig) -> Result<(ServerConfig, ServerConfig), AppError> {
+    // 壊れた設定ファイルは保存し直す
+    let stored = ServerConfig::load_file(app_data_dir)
+        .unwrap_or_default()
+        .with_changes(current, edited)
+        .map_err(AppError::internal)?;
+    let config = stored.with_env_overrides_or_self();
+    config.validate().map_err(|e| AppError::validation(None, e))?;
+    Ok((stored, config))
+}
+
+// 保存されたサーバーの設定（起動時の設定・認証・ログの保持）の取得
+#[tauri::command]
+fn get_server_settings(app_handle: AppHandle, state: State<ServerState>) -> Result<ServerSettings, AppError> {
+    let app_data_dir = app_handle.path().app_data_dir().map_err(AppError::internal)?;
+    let launch = LaunchSettings::load(&app_data_dir).map_err(AppError::storage)?;
+
+    let server = state.0.lock().unwrap();
+    Ok(ServerSettings::new(&launch, server.config(), server.auth().is_enabled()))
+}
+
+// サーバーの設定を検証してから保存する（ログの保持を変更した場合、起動中のサーバーは再起動される）
+// 保存や再起動に失敗した場合は、ファイルと実行中の設定を変更前に戻す
+// 検証から元に戻すまでを ServerLifecycle のロックを保持したまま行い、ほかの起動・停止・設定の変更と交互に実行しない
+#[tauri::command(async)]
+fn set_server_settings(
+    app_handle: AppHandle,
+    state: State<ServerState>,
+    lifecycle: State<ServerLifecycle>,
+    settings: ServerSettings,
+) -> Result<ServerSettings, AppError> {
+    let app_data_dir = app_handle.path().app_data_dir().map_err(AppError::internal)?;
+    let _lifecycle = lifecycle.0.lock().unwrap();
+
+    // 何も保存する前にすべての値を検証
+    let (current, was_running, previous_auth_enabled) = {
+        let server = state.0.lock().unwrap();
+        (server.config().clone(), server.is_running(), server.auth().is_enabled())
+    };
+    settings.validate(&current)?;
+    let config = settings.apply_to(&current);
+    let config_change = if config != current {
+        Some(stored_config(&app_data_dir, &current, &config)?)
+    } else {
+        None
+    };
+
+    let previous = PreviousSettings {
+        launch: LaunchSettings::load(&app_data_dir).map_err(AppError::storage)?,
+        auth_enabled: previous_auth_enabled,
+        stored: ServerConfig::load_file(&app_data_dir).unwrap_or_default(),
+        config: current,
+        was_running,
+    };
+    if let Err(e) = save_server_settings(&app_data_dir, &state, &settings, config_change) {
+        previous.restore(&app_data_dir, &state);
+        return Err(e);
+    }
+
+    let launch = LaunchSettings::load(&app_data_dir).map_err(AppError::storage)?;
+    let server = state.0.lock().unwrap();
+    Ok(ServerSettings::new(&launch, server.config(), server.auth().is_enabled()))
+}
+
+// 起動時の設定・認証・ログの保持を保存して反映する
+fn save_server_settings(
+    app_data_dir: &Path,
+    state: &ServerState,
+    settings: &ServerSettings,
+    config_change: Option<(ServerConfig, ServerConfig)>,
+) -> Result<(), AppError> {
+    let launch = settings.launch_settings();
+    launch.save(app_data_dir).map_err(AppError::storage)?;
+    {
+        let mut server = state.0.lock().unwrap();
+        server.set_preferred_port(launch.preferred_port());
+        server.auth().set_enabled(settings.auth_enabled).map_err(AppError::storage)?;
+    }
+
+    // ログの保持はサーバー設定ファイルに保存
+    if let Some((stored, config)) = config_change {
+        stored.save(app_data_dir).map_err(AppError::storage)?;
+        apply_config(state, config)?;
+    }
+    Ok(())
+}
+
+// set_server_settings の変更前の状態
+struct PreviousSettings {
+    launch: LaunchSettings,
+    auth_enabled: bool,
+    stored: ServerConfig,
+    config: ServerConfig,
+    was_running: bool,
+}
+
+impl PreviousSettings {
+    // 元に戻せなかった項目はエラーを出力して続ける（呼び出し元には最初のエラーを返す）
+    fn restore(&self, app_data_dir: &Path, state: &ServerState) {
+        if let Err(e) = self.launch.save(app_data_dir) {
+            eprintln!("Failed to restore launch settings: {}", e);
+        }
+        if let Err(e) = self.stored.save(app_data_dir) {
+            eprintln!("Failed to restore server config: {}", e);
+        }
+
+        // 実行中の設定を戻す（起動中の場合は stop_unlocked で停止し、待つ間はサーバーのロックを保持しない）
+        if state.0.lock().unwrap().config() != &self.config {
+            if let Err(e) = stop_unlocked(state) {
+                eprintln!("Failed to stop server to restore settings: {}", e);
+            }
+            if let Err(e) = state.0.lock().unwrap().update_config(self.config.clone()) {
+                eprintln!("Failed to restore server config: {}", e);
+            }
+        }
+
+        let mut server = state.0.lock().unwrap();
+        server.set_preferred_port(self.launch.preferred_port());
+        if let Err(e) = server.auth().set_enabled(self.auth_enabled) {
+            eprintln!("Failed to restore auth settings: {}", e);
+        }
+        if self.was_running && !server.is_running() {
+            if let Err(e) = server.start() {
+                eprintln!("Failed to restart server with the previous settings: {}", e);
+            }
+        }
+    }
+}
+
+// OpenRPCドキュメントをファイルに書き出す（現在のサーバー設定で生成）
+// スキーマの生成とファイルの書き込みでメインスレッドをブロックしないように別スレッドで実行する
+#[tauri::command(async)]
+fn export_openrpc(state: State<ServerState>, path: String) -> Result<(), AppError> {
+    if path.trim().is_empty() {
+        return Err(AppError::validation(Some("path"), "Path must not be empty"));
+    }
+
+    let document = {
+        let server = state.0.lock().unwrap();
+        openrpc::document(server.config())
+    };
+    let json = serde_json::to_string_pretty(&document).map_err(AppError::internal)?;
+    std::fs::write(&path, json)?;
+    Ok(())
+}
+
+// 共有トークンと認証の有効/無効の取得（スコープ付きトークンの値は返さない）
+#[tauri::command]
+fn get_auth_token(state: State<ServerState>) -> AuthStatus {
+    let server = state.0.lock().unwrap();
+    server.auth().status()
+}
+
+// 認証トークンの再生成（認証も有効になる）
+#[tauri::command]
+fn rotate_auth_token(state: State<ServerState>) -> Result<String, AppError> {
+    let server = state.0.lock().unwrap();
+    server.auth().rotate().map_err(AppError::storage)
+}
+
+// 認証の有効/無効の切り替え
+#[tauri::command]
+fn set_auth_enabled(state: State<ServerState>, enabled: bool) -> Result<(), AppError> {
+    let server = state.0.lock().unwrap();
+    server.auth().set_enabled(enabled).map_err(AppError::storage)
+}
+
+// スコープを限定したAPIトークンの作成（トークン値はこのときだけ返される）
+#[tauri::command]
+fn create_api_token(state: State<ServerState>, name: String, scopes: Vec<String>) -> Result<ApiToken, AppError> {
+    let server = state.0.lock().unwrap();
+    server.auth().create_token(name, scopes)
+}
+
+// APIトークンの一覧
+#[tauri::command]
+fn list_api_tokens(state: State<ServerState>) -> Vec<ApiTokenInfo> {
+    let server = state.0.lock().unwrap();
+    server.auth().list_tokens()
+}
+
+// APIトークンの失効
+#[tauri::command]
+fn revoke_api_token(state: State<ServerState>, id: String) -> Result<bool, AppError> {
+    let server = state.0.lock().unwrap();
+    server.auth().revoke_token(&id).map_err(AppError::storage)
 }
 
 // 新しいコマンド: ログ取得
@@ -45,9 +388,74 @@ fn get_logs(state: State<ServerState>) -> Vec<LogEntry> {
     server.get_logs()
 }
 
+// 保存されたログを期間（UNIX時刻の秒）を指定して古い順にページ単位で取得
+// ログファイルを読み込むため、メインスレッドをブロックしないように別スレッドで実行する（query_logs・export_logs も同様）
+#[tauri::command(async)]
+fn get_log_history(
+    state: State<ServerState>,
+    from: Option<u64>,
+    to: Option<u64>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<LogPage, AppError> {
+    let query = LogQuery {
+        from,
+        to,
+        order: Some(LogOrder::OldestFirst),
+        offset,
+        limit,
+        ..LogQuery::default()
+    };
+    let logger = state.0.lock().unwrap().logger();
+    logger.query(&query)
+}
+
+// 条件を指定してログを検索（デフォルトは新しい順）
+#[tauri::command(async)]
+fn query_logs(state: State<ServerState>, query: LogQuery) -> Result<LogPage, AppError> {
+    // ログファイルの読み込み中はサーバーのロックを保持しない
+    let logger = state.0.lock().unwrap().logger();
+    logger.query(&query)
+}
+
+// ログをHARまたはJSON Lines形式でファイルに書き出し、書き出した件数を返す
+#[tauri::command(async)]
+fn export_logs(
+    state: State<ServerState>,
+    path: String,
+    format: LogExportFormat,
+    source: Option<LogSource>,
+    query: Option<LogQuery>,
+) -> Result<usize, AppError> {
+    if path.trim().is_empty() {
+        return Err(AppError::validation(Some("path"), "Path must not be empty"));
+    }
+
+    let logger = state.0.lock().unwrap().logger();
+    let entries = logger.entries(source.unwrap_or_default(), query.as_ref())?;
+    std::fs::write(&path, export_entries(&entries, format)?)?;
+    Ok(entries.len())
+}
+
+// 記録されたリクエストを再実行し、元のレスポンスと比較する
+// データを変更するメソッドを含む場合は confirm を true にする必要がある
+// RPCハンドラーを実行するため、メインスレッドをブロックしないように別スレッドで実行する
+#[tauri::command(async)]
+fn replay_log_entry(state: State<ServerState>, request_id: String, confirm: Option<bool>) -> Result<ReplayResult, AppError> {
+    // ログファイルの読み込みと再実行の間はサーバーのロックを保持しない
+    let (io, logger) = {
+        let server = state.0.lock().unwrap();
+        (server.rpc_handler()?, server.logger())
+    };
+    let original = logger
+        .find(&request_id)?
+        .ok_or_else(|| AppError::not_found("log entry", &request_id))?;
+    replay::replay(&io, &original, confirm.unwrap_or(false))
+}
+
 // 新しいコマンド: ログクリア
 #[tauri::command]
-fn clear_logs(state: State<ServerState>) -> Result<(), String> {
+fn clear_logs(state: State<ServerState>) -> Result<(), AppError> {
     let server = state.0.lock().unwrap();
     server.clear_logs();
     Ok(())
@@ -65,18 +473,19 @@ fn get_note(notes_state: State<NotesState>, id: String) -> Option<Note> {
 }
 
 #[tauri::command]
-fn create_note(notes_state: State<NotesState>, title: String, content: String) -> Result<Note, String> {
-    notes_state.0.create(title, content).map_err(|e| e.to_string())
+fn create_note(notes_state: State<NotesState>, title: String, content: String) -> Result<Note, AppError> {
+    notes_state.0.create(title, content)
 }
 
+// expected_revision を指定すると、他で更新されていた場合に conflict エラーを返す
 #[tauri::command]
-fn update_note(notes_state: State<NotesState>, id: String, title: Option<String>, content: Option<String>) -> Result<Option<Note>, String> {
-    notes_state.0.update(&id, title, content).map_err(|e| e.to_string())
+fn update_note(notes_state: State<NotesState>, id: String, title: Option<String>, content: Option<String>, expected_revision: Option<u64>) -> Result<Note, AppError> {
+    notes_state.0.update(&id, title, content, expected_revision)
 }
 
 #[tauri::command]
-fn delete_note(notes_state: State<NotesState>, id: String) -> Result<bool, String> {
-    notes_state.0.delete(&id).map_err(|e| e.to_string())
+fn delete_note(notes_state: State<NotesState>, id: String) -> Result<bool, AppError> {
+    notes_state.0.delete(&id)
 }
 
 // Basic Tauri greet command (original example code)
@@ -88,6 +497,22 @@ fn greet(name: &str) -> String {
 // Tauri application entry point
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // --headless の場合はウィンドウを作らずにサーバーだけを起動する
+    match HeadlessOptions::from_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => {
+            if let Err(e) = headless::run(options) {
+                eprintln!("Headless server failed: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("Invalid arguments: {}", e);
+            std::process::exit(2);
+        }
+    }
+
     let server = JsonRpcServer::new();
     let server_state = ServerState(Arc::new(Mutex::new(server)));
 
@@ -95,27 +520,71 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .setup(|app| {
             // NoteStoreの初期化
-            let note_store = NoteStore::new(&app.handle()).expect("Failed to initialize note store");
-            app.manage(NotesState(Arc::new(note_store)));
+            let note_store = Arc::new(NoteStore::new(&app.handle()).expect("Failed to initialize note store"));
+            app.manage(NotesState(note_store.clone()));
             
-            // サーバーにアプリハンドルを設定
+            // サーバー設定の読み込み（設定ファイル + 環境変数）
+            // 設定ファイルが壊れていてもアプリは起動し、デフォルト値を使う
+            let app_data_dir = app.path().app_data_dir()?;
+            let server_config = ServerConfig::load_or_default(&app_data_dir);
+            let auth = TokenAuth::load(&app_data_dir).unwrap_or_else(|e| {
+                eprintln!("Failed to load auth token, using a temporary token: {}", e);
+                TokenAuth::unsaved()
+            });
+            let launch = LaunchSettings::load(&app_data_dir).unwrap_or_else(|e| {
+                eprintln!("Failed to load server settings, using defaults: {}", e);
+                LaunchSettings::default()
+            });
+
+            // サーバーにアプリハンドルと設定を設定
             {
                 // 一時的な値を変数に保存して、ライフタイムを延長する
                 let server_state = app.try_state::<ServerState>().unwrap();
                 let mut server = server_state.0.lock().unwrap();
                 server.set_app_handle(app.handle().clone());
+                server.set_note_store(note_store);
+                server.set_config(server_config);
+                server.set_auth(auth);
+                server.set_preferred_port(launch.preferred_port());
+
+                // 起動に失敗してもアプリは起動し、画面から起動し直せるようにする
+                if launch.auto_start {
+                    if let Err(e) = server.start() {
+                        eprintln!("Failed to start server on launch: {}", e);
+                    }
+                }
             }
             
             Ok(())
         })
         .manage(server_state)
+        .manage(ServerLifecycle::default())
         .invoke_handler(tauri::generate_handler![
             greet,
             start_server,
             stop_server,
+            restart_server,
             is_server_running,
             get_server_url,
+            get_ws_url,
+            get_ipc_path,
+            get_tls_fingerprint,
+            get_server_config,
+            set_server_config,
+            get_server_settings,
+            set_server_settings,
+            export_openrpc,
+            get_auth_token,
+            rotate_auth_token,
+            set_auth_enabled,
+            create_api_token,
+            list_api_tokens,
+            revoke_api_token,
             get_logs,
+            get_log_history,
+            query_logs,
+            export_logs,
+            replay_log_entry,
             clear_logs,
             list_notes,
             get_note,
@@ -123,6 +592,16 @@ pub fn run() {
             update_note,
             delete_note
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            // アプリの終了時は処理中のリクエストの完了を待ってからサーバーを停止する
+            if let tauri::RunEvent::Exit = event {
+                let lifecycle = app_handle.state::<ServerLifecycle>();
+                let _lifecycle = lifecycle.0.lock().unwrap();
+                if let Err(e) = stop_unlocked(&app_handle.state::<ServerState>()) {
+                    eprintln!("Failed to stop server on exit: {}", e);
+                }
+            }
+        });
 }
\ No newline at end of file
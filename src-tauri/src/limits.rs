@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// 保持するバケットがこの数を超えたら、満杯に戻ったバケットを削除する
+const MAX_IDLE_BUCKETS: usize = 1024;
+
+// クライアントごとのトークンバケット
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+// 制限を数える単位（トークンごと。認証なしのリクエストは接続元のアドレスとOriginの組み合わせごと）
+pub fn client_key(token_id: Option<&str>, peer: Option<IpAddr>, origin: Option<&str>) -> String {
+    match token_id {
+        Some(token_id) => format!("token:{}", token_id),
+        None => format!(
+            "anonymous:{}:{}",
+            peer.map(|ip| ip.to_string()).unwrap_or_else(|| "-".to_string()),
+            origin.unwrap_or("-"),
+        ),
+    }
+}
+
+// クライアントごとの1秒あたりのリクエスト数の制限
+pub struct RateLimiter {
+    per_sec: f64,
+    burst: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    // burst は連続して受け付けられる最大数
+    pub fn new(per_sec: u32, burst: u32) -> Self {
+        Self {
+            per_sec: f64::from(per_sec),
+            burst: f64::from(burst.max(1)),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // リクエストを1件消費する。制限を超えた場合は次に受け付けられるまでの時間を返す
+    pub fn check(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > MAX_IDLE_BUCKETS {
+            let (per_sec, burst) = (self.per_sec, self.burst);
+            buckets.retain(|_, bucket| {
+                bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * per_sec < burst
+            });
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.per_sec).min(self.burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.per_sec))
+        }
+    }
+}
+
+// 同時に処理するリクエスト数の制限
+pub struct ConcurrencyLimit {
+    max: usize,
+    current: Arc<AtomicUsize>,
+}
+
+impl ConcurrencyLimit {
+    pub fn new(max: usize) -> Self {
+        Self {
+            max,
+            current: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    // 上限に達していなければ枠を確保する（Permitを破棄すると解放される）
+    pub fn try_acquire(&self) -> Option<Permit> {
+        self.current
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                (current < self.max).then_some(current + 1)
+            })
+            .ok()
+            .map(|_| Permit(self.current.clone()))
+    }
+}
+
+// 確保した処理枠
+pub struct Permit(Arc<AtomicUsize>);
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let limiter = RateLimiter::new(2, 3);
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.check("a", now).is_ok());
+        }
+        assert_eq!(limiter.check("a", now), Err(Duration::from_millis(500)));
+        // 別のクライアントは影響を受けない
+        assert!(limiter.check("b", now).is_ok());
+        // 0.5秒で1件分補充される
+        assert!(limiter.check("a", now + Duration::from_millis(500)).is_ok());
+        assert!(limiter.check("a", now + Duration::from_millis(500)).is_err());
+    }
+
+    #[test]
+    fn test_anonymous_clients_have_separate_budgets() {
+        let limiter = RateLimiter::new(1, 1);
+        let now = Instant::now();
+        let first = client_key(None, Some(IpAddr::from([127, 0, 0, 1])), None);
+        let second = client_key(None, Some(IpAddr::from([192, 168, 0, 2])), None);
+        let browser = client_key(None, Some(IpAddr::from([127, 0, 0, 1])), Some("http://localhost:1420"));
+
+        assert!(limiter.check(&first, now).is_ok());
+        assert!(limiter.check(&first, now).is_err());
+        // 別のアドレス・別のOriginからのリクエストは制限されない
+        assert!(limiter.check(&second, now).is_ok());
+        assert!(limiter.check(&browser, now).is_ok());
+        // トークンIDがある場合はアドレスに関係なく同じ単位で数える
+        assert_eq!(
+            client_key(Some("ci"), Some(IpAddr::from([127, 0, 0, 1])), None),
+            client_key(Some("ci"), Some(IpAddr::from([192, 168, 0, 2])), None),
+        );
+    }
+
+    #[test]
+    fn test_concurrency_limit() {
+        let limit = ConcurrencyLimit::new(1);
+        let permit = limit.try_acquire();
+        assert!(permit.is_some());
+        assert!(limit.try_acquire().is_none());
+        drop(permit);
+        assert!(limit.try_acquire().is_some());
+    }
+}
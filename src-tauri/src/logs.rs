@@ -0,0 +1,1257 @@
+use jsonrpc_core::{Call, Error, Id, Output, Request, Response};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, RwLock, Weak};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::config::ServerConfig;
+use crate::error::AppError;
+use crate::redact::Redaction;
+
+// ログに記録するリクエスト・レスポンス本文の上限（超えた分は切り捨てる）
+pub const MAX_LOGGED_PAYLOAD_BYTES: usize = 64 * 1024;
+
+// バッチの各呼び出しについて記録するパラメータ・結果の上限
+const MAX_LOGGED_ITEM_BYTES: usize = 4 * 1024;
+
+// メモリに保持するログの件数のデフォルト値
+pub const DEFAULT_LOG_MEMORY_ENTRIES: usize = 50;
+
+// ログファイル名（データディレクトリの logs に保存し、ローテーションしたファイルは requests-<時刻>.jsonl になる）
+const LOG_FILE_NAME: &str = "requests.jsonl";
+const ROTATED_FILE_PREFIX: &str = "requests-";
+const ROTATED_FILE_SUFFIX: &str = ".jsonl";
+// 書き込み中のファイルを作り始めた時刻（UNIX時刻の秒）
+// 作成時刻を記録しないファイルシステムもあり、更新時刻は書き込みのたびに変わるため別のファイルに保存する
+const LOG_OPENED_FILE_NAME: &str = "requests.opened";
+
+// 短時間に続いたログの通知を1回のイベントにまとめる間隔
+const NOTIFY_INTERVAL: Duration = Duration::from_millis(100);
+
+// ログ履歴の1ページの件数（デフォルト値と最大値）
+const DEFAULT_HISTORY_PAGE_SIZE: usize = 100;
+const MAX_HISTORY_PAGE_SIZE: usize = 500;
+
+// クライアントが指定したリクエストIDとして受け付ける最大の長さ
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+// 受信したリクエストごとに割り当てるID
+pub fn new_request_id() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+// クライアントが X-Request-Id で指定したIDを検証（使えない場合はNone）
+pub fn sanitize_request_id(value: &str) -> Option<String> {
+    let value = value.trim();
+    let valid = !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LEN
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+    valid.then(|| value.to_string())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
+pub struct LogEntry {
+    // サーバーが生成したリクエストID（HTTPでは X-Request-Id ヘッダーで返す）
+    pub(crate) request_id: String,
+    // クライアントが X-Request-Id で指定したID（X-Client-Request-Id ヘッダーで返す）
+    #[serde(default)]
+    pub(crate) client_request_id: Option<String>,
+    pub(crate) timestamp: u64,
+    pub(crate) method: String,
+    pub(crate) uri: String,
+    // HTTPのリクエストのスキーム（http / https）。WebSocket・IPCではNone
+    #[serde(default)]
+    pub(crate) scheme: Option<String>,
+    #[serde(deserialize_with = "deserialize_headers")]
+    pub(crate) headers: Vec<LogHeader>,
+    // 受信したリクエスト本文（HTTPは受信したバイト列そのもの）
+    pub(crate) body: Option<String>,
+    // 送信したレスポンス本文
+    pub(crate) response: Option<String>,
+    // レスポンスのHTTPステータス（WebSocket・IPCのメッセージではNone）
+    pub(crate) status: Option<u16>,
+    // 呼び出しに使われたAPIトークンのID
+    pub(crate) token_id: Option<String>,
+    // リクエストの Origin ヘッダー
+    #[serde(default)]
+    pub(crate) origin: Option<String>,
+    // JSON-RPCの id とメソッド名（単一の呼び出しの場合）
+    #[schemars(with = "Option<serde_json::Value>")]
+    pub(crate) rpc_id: Option<Id>,
+    pub(crate) rpc_method: Option<String>,
+    // 切り捨て前の本文のバイト数
+    pub(crate) body_size: Option<usize>,
+    pub(crate) response_size: Option<usize>,
+    // 本文が上限を超えて切り捨てられたか
+    pub(crate) body_truncated: bool,
+    pub(crate) response_truncated: bool,
+    // リクエストを受け付けてからレスポンスを返すまでの時間
+    pub(crate) elapsed_ms: Option<u64>,
+    // バッチリクエストの各呼び出し（単一の呼び出しでは空）
+    #[serde(default)]
+    pub(crate) items: Vec<BatchItem>,
+    // ログから再実行したリクエストの場合は元のリクエストID
+    #[serde(default)]
+    pub(crate) replay_of: Option<String>,
+}
+
+// バッチリクエストに含まれる1件の呼び出しの記録
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
+pub struct BatchItem {
+    // バッチ内の位置（0から）
+    pub(crate) index: usize,
+    #[schemars(with = "Option<serde_json::Value>")]
+    pub(crate) rpc_id: Option<Id>,
+    // 不正な呼び出しの場合はNone
+    pub(crate) rpc_method: Option<String>,
+    pub(crate) params: Option<String>,
+    pub(crate) result: Option<String>,
+    #[schemars(with = "Option<serde_json::Value>")]
+    pub(crate) error: Option<Error>,
+    // パラメータまたは結果が上限を超えて切り捨てられたか
+    pub(crate) truncated: bool,
+    // 呼び出しが完了していない場合はNone
+    pub(crate) elapsed_ms: Option<u64>,
+}
+
+// リクエストヘッダー（受信した順）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+pub struct LogHeader {
+    pub name: String,
+    pub value: String,
+}
+
+impl LogHeader {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+}
+
+// 以前の形式（"name: \"value\", " を連結した文字列）で保存されたログも読み込めるようにする
+fn deserialize_headers<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<LogHeader>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Headers {
+        List(Vec<LogHeader>),
+        Legacy(String),
+    }
+
+    Ok(match Headers::deserialize(deserializer)? {
+        Headers::List(headers) => headers,
+        Headers::Legacy(headers) => headers
+            .split("\", ")
+            .filter_map(|pair| {
+                let (name, value) = pair.split_once(": ")?;
+                Some(LogHeader::new(name.trim(), value.trim_start_matches('"').trim_end_matches("\", ")))
+            })
+            .collect(),
+    })
+}
+
+impl LogEntry {
+    pub fn new(request_id: String, method: String, uri: String, headers: Vec<LogHeader>, token_id: Option<String>) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        Self {
+            request_id,
+            client_request_id: None,
+            timestamp,
+            method,
+            uri,
+            scheme: None,
+            headers,
+            body: None,
+            response: None,
+            status: None,
+            token_id,
+            origin: None,
+            rpc_id: None,
+            rpc_method: None,
+            body_size: None,
+            response_size: None,
+            body_truncated: false,
+            response_truncated: false,
+            elapsed_ms: None,
+            items: Vec::new(),
+            replay_of: None,
+        }
+    }
+
+    // 本文は記録時（Logger::prepare）に秘匿情報を取り除いてから切り捨てる
+    pub fn with_body(mut self, body: &str) -> Self {
+        self.body_size = Some(body.len());
+        self.body = Some(body.to_string());
+        self
+    }
+
+    pub fn with_origin(mut self, origin: Option<String>) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn with_scheme(mut self, scheme: &str) -> Self {
+        self.scheme = Some(scheme.to_string());
+        self
+    }
+
+    pub fn with_client_request_id(mut self, client_request_id: Option<String>) -> Self {
+        self.client_request_id = client_request_id;
+        self
+    }
+
+    pub fn with_replay_of(mut self, replay_of: Option<String>) -> Self {
+        self.replay_of = replay_of;
+        self
+    }
+
+    // JSON-RPCの id とメソッド名を記録
+    pub fn with_call(mut self, request: &Request) -> Self {
+        if let Request::Single(call) = request {
+            let (id, method) = match call {
+                Call::MethodCall(method_call) => (Some(method_call.id.clone()), Some(method_call.method.clone())),
+                Call::Notification(notification) => (None, Some(notification.method.clone())),
+                Call::Invalid { id } => (Some(id.clone()), None),
+            };
+            self.rpc_id = id;
+            self.rpc_method = method;
+        }
+        self
+    }
+
+    pub fn with_response(mut self, response: &str) -> Self {
+        self.response_size = Some(response.len());
+        self.response = Some(response.to_string());
+        self
+    }
+
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn with_elapsed(mut self, elapsed: Duration) -> Self {
+        self.elapsed_ms = Some(elapsed.as_millis() as u64);
+        self
+    }
+
+    // バッチの各呼び出しの記録を追加
+    pub fn with_batch(mut self, batch: &BatchLog) -> Self {
+        self.items = batch.items();
+        self
+    }
+
+    // 本文とバッチの各呼び出しのパラメータ・結果を上限で切り捨てる
+    fn truncate_payloads(&mut self) {
+        self.body_truncated |= truncate_payload(&mut self.body, MAX_LOGGED_PAYLOAD_BYTES);
+        self.response_truncated |= truncate_payload(&mut self.response, MAX_LOGGED_PAYLOAD_BYTES);
+        for item in &mut self.items {
+            item.truncated |= truncate_payload(&mut item.params, MAX_LOGGED_ITEM_BYTES);
+            item.truncated |= truncate_payload(&mut item.result, MAX_LOGGED_ITEM_BYTES);
+        }
+    }
+
+    // レスポンスに含まれるJSON-RPCのエラーコード（バッチの場合は各呼び出しのエラー）
+    fn error_codes(&self) -> Vec<i64> {
+        if !self.items.is_empty() {
+            return self
+                .items
+                .iter()
+                .filter_map(|item| item.error.as_ref().map(|error| error.code.code()))
+                .collect();
+        }
+
+        match self.response.as_deref().map(serde_json::from_str::<Response>) {
+            Some(Ok(Response::Single(Output::Failure(failure)))) => vec![failure.error.code.code()],
+            _ => Vec::new(),
+        }
+    }
+}
+
+// ログの並び順
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LogOrder {
+    #[default]
+    NewestFirst,
+    OldestFirst,
+}
+
+impl LogOrder {
+    // パラメータとして受け付ける名前（serde の名前と揃える）
+    pub const NAMES: &'static [&'static str] = &["newest_first", "oldest_first"];
+}
+
+// ログの検索条件（指定した条件をすべて満たすエントリを返す）
+#[derive(Deserialize, Debug, Clone, Default, JsonSchema)]
+#[serde(default)]
+pub struct LogQuery {
+    // 期間（UNIX時刻の秒、両端を含む）
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    // HTTPメソッド（WebSocket・IPCでは WS / IPC）。大文字・小文字を区別しない
+    pub method: Option<String>,
+    // JSON-RPCのメソッド名（バッチの場合はいずれかの呼び出し）
+    pub rpc_method: Option<String>,
+    // HTTPステータス
+    pub status: Option<u16>,
+    // JSON-RPCのエラーコード
+    pub error_code: Option<i64>,
+    // リクエスト本文とレスポンス本文に含まれる文字列（大文字・小文字を区別しない）
+    pub text: Option<String>,
+    // 呼び出しに使われたAPIトークンのID
+    pub token_id: Option<String>,
+    pub origin: Option<String>,
+    pub order: Option<LogOrder>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+impl LogQuery {
+    fn matches(&self, entry: &LogEntry, text: Option<&str>) -> bool {
+        self.from.is_none_or(|from| entry.timestamp >= from)
+            && self.to.is_none_or(|to| entry.timestamp <= to)
+            && self
+                .method
+                .as_ref()
+                .is_none_or(|method| entry.method.eq_ignore_ascii_case(method))
+            && self.rpc_method.as_ref().is_none_or(|rpc_method| {
+                entry.rpc_method.as_ref() == Some(rpc_method)
+                    || entry.items.iter().any(|item| item.rpc_method.as_ref() == Some(rpc_method))
+            })
+            && self.status.is_none_or(|status| entry.status == Some(status))
+            && self.error_code.is_none_or(|code| entry.error_codes().contains(&code))
+            && text.is_none_or(|text| {
+                [&entry.body, &entry.response]
+                    .into_iter()
+                    .flatten()
+                    .any(|payload| payload.to_lowercase().contains(text))
+            })
+            && self.token_id.as_ref().is_none_or(|token_id| entry.token_id.as_ref() == Some(token_id))
+            && self.origin.as_ref().is_none_or(|origin| entry.origin.as_ref() == Some(origin))
+    }
+}
+
+// バッチリクエストの各呼び出しを集める（JSON-RPCミドルウェアの on_call から記録する）
+#[derive(Debug, Default)]
+pub struct BatchLog {
+    items: Mutex<Vec<BatchItem>>,
+}
+
+impl BatchLog {
+    // 呼び出しの開始を記録し、完了時に渡す位置を返す
+    // バッチの呼び出しは並行して処理されるが、on_call はバッチ内の順序で呼ばれる
+    pub fn start(&self, call: &Call) -> usize {
+        let (rpc_id, rpc_method, params) = match call {
+            Call::MethodCall(method_call) => (
+                Some(method_call.id.clone()),
+                Some(method_call.method.clone()),
+                serde_json::to_string(&method_call.params).ok(),
+            ),
+            Call::Notification(notification) => (
+                None,
+                Some(notification.method.clone()),
+                serde_json::to_string(&notification.params).ok(),
+            ),
+            Call::Invalid { id } => (Some(id.clone()), None, None),
+        };
+
+        let mut items = self.items.lock().unwrap();
+        let index = items.len();
+        items.push(BatchItem {
+            index,
+            rpc_id,
+            rpc_method,
+            params,
+            result: None,
+            error: None,
+            truncated: false,
+            elapsed_ms: None,
+        });
+        index
+    }
+
+    // 呼び出しの完了を記録（通知の場合は output がNone）
+    pub fn finish(&self, index: usize, output: Option<&Output>, elapsed: Duration) {
+        let mut items = self.items.lock().unwrap();
+        let Some(item) = items.get_mut(index) else {
+            return;
+        };
+
+        match output {
+            Some(Output::Success(success)) => item.result = serde_json::to_string(&success.result).ok(),
+            Some(Output::Failure(failure)) => item.error = Some(failure.error.clone()),
+            None => {}
+        }
+        item.elapsed_ms = Some(elapsed.as_millis() as u64);
+    }
+
+    fn items(&self) -> Vec<BatchItem> {
+        self.items.lock().unwrap().clone()
+    }
+}
+
+// ログの取得元
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogSource {
+    // メモリに保持している直近のログ
+    #[default]
+    Memory,
+    // ファイルに保存したすべてのログ（log_persist が有効な場合のみ）
+    Persisted,
+}
+
+// ログの書き出し形式
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogExportFormat {
+    // HAR 1.2（ブラウザの開発者ツールで読み込める）
+    Har,
+    // 1行に1件のエントリ
+    Jsonl,
+}
+
+// ログエントリを書き出し形式の文字列に変換
+pub fn export_entries(entries: &[LogEntry], format: LogExportFormat) -> Result<String, AppError> {
+    match format {
+        LogExportFormat::Har => serde_json::to_string_pretty(&crate::har::document(entries)).map_err(AppError::internal),
+        LogExportFormat::Jsonl => {
+            let mut output = String::new();
+            for entry in entries {
+                output.push_str(&serde_json::to_string(entry).map_err(AppError::internal)?);
+                output.push('\n');
+            }
+            Ok(output)
+        }
+    }
+}
+
+// 検索結果の1ページ
+#[derive(Serialize, Debug, Clone, JsonSchema)]
+pub struct LogPage {
+    entries: Vec<LogEntry>,
+    // 続きがある場合に次のページの取得に使う offset
+    next_offset: Option<usize>,
+}
+
+// JSON Lines形式のログファイル（サイズまたは経過時間でローテーションする）
+struct LogFile {
+    dir: PathBuf,
+    max_file_bytes: u64,
+    max_file_age: Option<Duration>,
+    // 残すローテーション済みファイルの数
+    retention_files: usize,
+    // 書き込み中のファイルとそのサイズ・作り始めた時刻（最初の書き込みで開く）
+    writer: Option<(File, u64, SystemTime)>,
+}
+
+impl LogFile {
+    fn new(dir: PathBuf, config: &ServerConfig) -> Self {
+        Self {
+            dir,
+            max_file_bytes: config.log_max_file_bytes,
+            max_file_age: config.log_max_file_age_secs.map(Duration::from_secs),
+            retention_files: config.log_retention_files,
+            writer: None,
+        }
+    }
+
+    fn append(&mut self, entry: &LogEntry) -> io::Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        if self.writer.is_none() {
+            self.open()?;
+        }
+        if self.needs_rotation(line.len() as u64) {
+            self.rotate()?;
+            self.open()?;
+        }
+
+        if let Some((file, size, _)) = self.writer.as_mut() {
+            file.write_all(line.as_bytes())?;
+            *size += line.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn open(&mut self) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(LOG_FILE_NAME))?;
+        let size = file.metadata()?.len();
+
+        // 空のファイルは新しく作り始めたものとして時刻を記録し直す
+        let opened_path = self.dir.join(LOG_OPENED_FILE_NAME);
+        let opened = match fs::read_to_string(&opened_path).ok().and_then(|secs| secs.trim().parse::<u64>().ok()) {
+            Some(secs) if size > 0 => UNIX_EPOCH + Duration::from_secs(secs),
+            _ => {
+                let now = SystemTime::now();
+                let secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+                fs::write(&opened_path, secs.to_string())?;
+                now
+            }
+        };
+        self.writer = Some((file, size, opened));
+        Ok(())
+    }
+
+    fn needs_rotation(&self, incoming: u64) -> bool {
+        let Some((_, size, opened)) = &self.writer else {
+            return false;
+        };
+        if *size == 0 {
+            return false;
+        }
+
+        let too_large = size + incoming > self.max_file_bytes;
+        let too_old = match self.max_file_age {
+            Some(max_age) => opened.elapsed().map(|age| age >= max_age).unwrap_or(false),
+            None => false,
+        };
+        too_large || too_old
+    }
+
+    // 書き込み中のファイルをリネームし、保持数を超えた古いファイルを削除
+    fn rotate(&mut self) -> io::Result<()> {
+        self.writer = None;
+
+        // 同じミリ秒にローテーションした場合は既存のファイルを上書きしないよう時刻をずらす
+        let mut millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let mut rotated = self.rotated_path(millis);
+        while rotated.exists() {
+            millis += 1;
+            rotated = self.rotated_path(millis);
+        }
+        fs::rename(self.dir.join(LOG_FILE_NAME), rotated)?;
+
+        let rotated_files = rotated_files(&self.dir)?;
+        let excess = rotated_files.len().saturating_sub(self.retention_files);
+        for path in &rotated_files[..excess] {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    fn rotated_path(&self, millis: u128) -> PathBuf {
+        self.dir
+            .join(format!("{}{:013}{}", ROTATED_FILE_PREFIX, millis, ROTATED_FILE_SUFFIX))
+    }
+}
+
+// ローテーション済みのファイル（古い順）
+fn rotated_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .map(|name| name.starts_with(ROTATED_FILE_PREFIX) && name.ends_with(ROTATED_FILE_SUFFIX))
+                    .unwrap_or(false)
+            })
+            .collect(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e),
+    };
+    files.sort();
+    Ok(files)
+}
+
+// 保存されているログファイル（古い順）と、ファイル内のエントリのタイムスタンプの上限（書き込み中のファイルはNone）
+// ローテーション済みのファイル名の時刻はそのファイルに書き込まれた最後のエントリより後になる
+fn log_files(dir: &Path) -> io::Result<Vec<(PathBuf, Option<u64>)>> {
+    let mut files: Vec<(PathBuf, Option<u64>)> = rotated_files(dir)?
+        .into_iter()
+        .map(|path| {
+            let latest = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(ROTATED_FILE_PREFIX)?.strip_suffix(ROTATED_FILE_SUFFIX))
+                .and_then(|millis| millis.parse::<u64>().ok())
+                .map(|millis| millis / 1000);
+            (path, latest)
+        })
+        .collect();
+    let current = dir.join(LOG_FILE_NAME);
+    if current.exists() {
+        files.push((current, None));
+    }
+    Ok(files)
+}
+
+// 1つのログファイルのエントリを書き込んだ順に読み込む（壊れた行は読み飛ばす）
+fn read_log_file(path: &Path) -> io::Result<Vec<LogEntry>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        // 読み込み中にローテーションで削除された場合
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect())
+}
+
+// 保存されたエントリを指定した順に1ファイルずつ読み込み、visit が false を返したところで止める
+// from より前のエントリしか含まないファイルは読み込まない
+// 読み込み中も書き込みを止めないよう、ロックは保持しない
+fn scan_log_files(dir: &Path, order: LogOrder, from: Option<u64>, mut visit: impl FnMut(LogEntry) -> bool) -> io::Result<()> {
+    let mut files = log_files(dir)?;
+    files.retain(|(_, latest)| from.is_none_or(|from| latest.is_none_or(|latest| latest >= from)));
+    if order == LogOrder::NewestFirst {
+        files.reverse();
+    }
+
+    for (path, _) in files {
+        let mut entries = read_log_file(&path)?;
+        if order == LogOrder::NewestFirst {
+            entries.reverse();
+        }
+        for entry in entries {
+            if !visit(entry) {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+// ログの変更の通知
+#[derive(Debug, Clone)]
+pub enum LogEvent {
+    // 追加・更新されたエントリ（同じリクエストの更新はまとめて最新のエントリになる）
+    Appended(Vec<LogEntry>),
+    Cleared,
+}
+
+// ログの変更を受け取るリスナーの型
+pub type LogListener = Box<dyn Fn(LogEvent) + Send + Sync>;
+
+// 通知を待っているエントリ
+#[derive(Default)]
+struct PendingLogs {
+    entries: Vec<LogEntry>,
+    flush_scheduled: bool,
+}
+
+// ログの変更をリスナーに通知する（大量のリクエストでフロントエンドが溢れないよう一定間隔でまとめる）
+#[derive(Default)]
+struct LogNotifier {
+    listener: Mutex<Option<LogListener>>,
+    pending: Mutex<PendingLogs>,
+    // 通知スレッドを起こすチャネル（最初の通知で通知スレッドを起動し、以降は同じスレッドを使う）
+    wake: Mutex<Option<mpsc::Sender<()>>>,
+}
+
+impl LogNotifier {
+    fn push(self: &Arc<Self>, entry: LogEntry, limit: usize) {
+        if self.listener.lock().unwrap().is_none() {
+            return;
+        }
+
+        let mut pending = self.pending.lock().unwrap();
+        match pending
+            .entries
+            .iter_mut()
+            .find(|pending_entry| pending_entry.request_id == entry.request_id)
+        {
+            Some(pending_entry) => *pending_entry = entry,
+            None => pending.entries.push(entry),
+        }
+
+        // 通知が追いつかない場合は古いエントリから捨てる（メモリに保持する件数を超えて送らない）
+        let excess = pending.entries.len().saturating_sub(limit);
+        pending.entries.drain(..excess);
+
+        if !pending.flush_scheduled {
+            pending.flush_scheduled = true;
+            self.wake();
+        }
+    }
+
+    fn wake(self: &Arc<Self>) {
+        let mut wake = self.wake.lock().unwrap();
+        let sender = wake.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel();
+            let notifier = Arc::downgrade(self);
+            thread::spawn(move || run_notifier(notifier, receiver));
+            sender
+        });
+        let _ = sender.send(());
+    }
+
+    fn flush(&self) {
+        let entries = {
+            let mut pending = self.pending.lock().unwrap();
+            pending.flush_scheduled = false;
+            std::mem::take(&mut pending.entries)
+        };
+
+        if !entries.is_empty() {
+            self.notify(LogEvent::Appended(entries));
+        }
+    }
+
+    fn cleared(&self) {
+        self.pending.lock().unwrap().entries.clear();
+        self.notify(LogEvent::Cleared);
+    }
+
+    fn notify(&self, event: LogEvent) {
+        if let Some(listener) = self.listener.lock().unwrap().as_ref() {
+            listener(event);
+        }
+    }
+}
+
+// 通知スレッド（起こされてから一定間隔の間に追加されたエントリをまとめて通知する）
+// LogNotifier が破棄されるとチャネルが閉じて終了する
+fn run_notifier(notifier: Weak<LogNotifier>, receiver: mpsc::Receiver<()>) {
+    while receiver.recv().is_ok() {
+        let deadline = Instant::now() + NOTIFY_INTERVAL;
+        loop {
+            match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(()) => continue,
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        let Some(notifier) = notifier.upgrade() else {
+            return;
+        };
+        notifier.flush();
+    }
+}
+
+// ログ記録用の構造体
+pub(crate) struct Logger {
+    logs: Mutex<VecDeque<LogEntry>>,
+    memory_entries: AtomicUsize,
+    // ログをファイルに保存する場合のみSome
+    file: Mutex<Option<LogFile>>,
+    notifier: Arc<LogNotifier>,
+    // 完了したエントリを1件ずつ送るチャネル（通知と違い、まとめたり捨てたりしない）
+    completed: Mutex<Option<mpsc::Sender<LogEntry>>>,
+    redaction: RwLock<Redaction>,
+}
+
+impl Logger {
+    pub(crate) fn new() -> Self {
+        Self {
+            logs: Mutex::new(VecDeque::new()),
+            memory_entries: AtomicUsize::new(DEFAULT_LOG_MEMORY_ENTRIES),
+            file: Mutex::new(None),
+            notifier: Arc::new(LogNotifier::default()),
+            completed: Mutex::new(None),
+            redaction: RwLock::new(Redaction::new(&ServerConfig::default())),
+        }
+    }
+
+    // ログの変更を受け取るリスナーを設定（フロントエンドへのイベント送信に使う）
+    pub(crate) fn set_listener(&self, listener: LogListener) {
+        *self.notifier.listener.lock().unwrap() = Some(listener);
+    }
+
+    // 完了したリクエストのエントリを記録した順にすべて受け取る（受け取り側を閉じると送信をやめる）
+    pub(crate) fn subscribe_completed(&self) -> mpsc::Receiver<LogEntry> {
+        let (sender, receiver) = mpsc::channel();
+        *self.completed.lock().unwrap() = Some(sender);
+        receiver
+    }
+
+    // 一定間隔でまとめている通知をすぐに送る（停止時に最後のログを取りこぼさないようにする）
+    pub(crate) fn flush_notifications(&self) {
+        self.notifier.flush();
+    }
+
+    // 処理を開始したリクエストを通知（完了時に record で同じリクエストIDのエントリに置き換わる）
+    pub(crate) fn started(&self, log_entry: &LogEntry) {
+        let log_entry = self.prepare(log_entry.clone());
+        self.notifier.push(log_entry, self.memory_entries.load(Ordering::Relaxed));
+    }
+
+    // 秘匿情報を取り除いてから本文を切り捨てる（メモリ・ファイル・イベントに渡す前に必ず通す）
+    fn prepare(&self, mut log_entry: LogEntry) -> LogEntry {
+        self.redaction.read().unwrap().apply(&mut log_entry);
+        log_entry.truncate_payloads();
+        log_entry
+    }
+
+    // サーバー設定のログ関連の値を反映（log_dir はログファイルを保存するディレクトリ）
+    pub(crate) fn configure(&self, config: &ServerConfig, log_dir: Option<&Path>) {
+        self.memory_entries.store(config.log_memory_entries, Ordering::Relaxed);
+        *self.redaction.write().unwrap() = Redaction::new(config);
+        {
+            let mut logs = self.logs.lock().unwrap();
+            while logs.len() > config.log_memory_entries {
+                logs.pop_front();
+            }
+        }
+
+        let mut file = self.file.lock().unwrap();
+        *file = match log_dir {
+            Some(dir) if config.log_persist => Some(LogFile::new(dir.to_path_buf(), config)),
+            _ => None,
+        };
+    }
+
+    // 完了したリクエストを記録
+    pub(crate) fn record(&self, log_entry: LogEntry) {
+        let log_entry = self.prepare(log_entry);
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            // ファイルへの書き込みに失敗してもリクエストの処理は続ける
+            if let Err(e) = file.append(&log_entry) {
+                eprintln!("Failed to write request log: {}", e);
+            }
+        }
+
+        {
+            let mut completed = self.completed.lock().unwrap();
+            if completed.as_ref().is_some_and(|sender| sender.send(log_entry.clone()).is_err()) {
+                *completed = None;
+            }
+        }
+
+        let memory_entries = self.memory_entries.load(Ordering::Relaxed);
+        self.notifier.push(log_entry.clone(), memory_entries);
+
+        let mut logs = self.logs.lock().unwrap();
+        logs.push_back(log_entry);
+
+        while logs.len() > memory_entries {
+            logs.pop_front();
+        }
+    }
+
+    // ミドルウェアで拒否したリクエストを記録
+    pub(crate) fn log_rejected(&self, log_entry: LogEntry, status: u16, reason: String) {
+        self.record(log_entry.with_status(status).with_response(&reason));
+    }
+
+    pub(crate) fn get_logs(&self) -> Vec<LogEntry> {
+        let logs = self.logs.lock().unwrap();
+        logs.iter().cloned().collect()
+    }
+
+    // メモリ上のログのみ削除（ファイルに保存したログは残る）
+    pub(crate) fn clear_logs(&self) {
+        self.logs.lock().unwrap().clear();
+        self.notifier.cleared();
+    }
+
+    // 取得元のログのうち条件に一致するものをすべて古い順に取得（offset・limit・order は無視する）
+    pub(crate) fn entries(&self, source: LogSource, query: Option<&LogQuery>) -> Result<Vec<LogEntry>, AppError> {
+        let text = query.and_then(|query| query.text.as_ref()).map(|text| text.to_lowercase());
+        let matches = |entry: &LogEntry| query.is_none_or(|query| query.matches(entry, text.as_deref()));
+
+        match source {
+            LogSource::Memory => Ok(self.get_logs().into_iter().filter(matches).collect()),
+            LogSource::Persisted => {
+                let log_dir = self
+                    .log_dir()
+                    .ok_or_else(|| AppError::validation(Some("source"), "Log persistence is disabled"))?;
+                let mut entries = Vec::new();
+                let from = query.and_then(|query| query.from);
+                scan_log_files(&log_dir, LogOrder::OldestFirst, from, |entry| {
+                    if matches(&entry) {
+                        entries.push(entry);
+                    }
+                    true
+                })?;
+                Ok(entries)
+            }
+        }
+    }
+
+    // リクエストIDでログエントリを取得（メモリ上にない場合は保存されたログを新しい順に探す）
+    pub(crate) fn find(&self, request_id: &str) -> Result<Option<LogEntry>, AppError> {
+        let found = self
+            .logs
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|entry| entry.request_id == request_id)
+            .cloned();
+        if found.is_some() {
+            return Ok(found);
+        }
+
+        let mut found = None;
+        if let Some(dir) = self.log_dir() {
+            scan_log_files(&dir, LogOrder::NewestFirst, None, |entry| {
+                if entry.request_id == request_id {
+                    found = Some(entry);
+                }
+                found.is_none()
+            })?;
+        }
+        Ok(found)
+    }
+
+    // 条件に一致するログをページ単位で取得
+    // ファイルに保存している場合は保存されたすべてのログ、保存していない場合はメモリ上のログから検索する
+    // 指定した順にファイルを読み込み、ページを埋めたところで読み込みをやめる
+    pub(crate) fn query(&self, query: &LogQuery) -> Result<LogPage, AppError> {
+        let offset = query.offset.unwrap_or(0);
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_HISTORY_PAGE_SIZE)
+            .clamp(1, MAX_HISTORY_PAGE_SIZE);
+        let text = query.text.as_ref().map(|text| text.to_lowercase());
+        let order = query.order.unwrap_or_default();
+
+        // 次のページの有無を判定するため1件多く取得する
+        let wanted = offset + limit + 1;
+        let mut matched = Vec::new();
+        let mut visit = |entry: LogEntry| {
+            if query.matches(&entry, text.as_deref()) {
+                matched.push(entry);
+            }
+            matched.len() < wanted
+        };
+
+        match self.log_dir() {
+            Some(dir) => scan_log_files(&dir, order, query.from, visit)?,
+            None => {
+                let mut logs = self.get_logs();
+                if order == LogOrder::NewestFirst {
+                    logs.reverse();
+                }
+                for entry in logs {
+                    if !visit(entry) {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let mut entries: Vec<LogEntry> = matched.into_iter().skip(offset).collect();
+        let next_offset = (entries.len() > limit).then(|| offset + limit);
+        entries.truncate(limit);
+        Ok(LogPage { entries, next_offset })
+    }
+
+    // ログファイルを保存するディレクトリ（保存しない場合はNone）
+    fn log_dir(&self) -> Option<PathBuf> {
+        self.file.lock().unwrap().as_ref().map(|file| file.dir.clone())
+    }
+}
+
+// 本文を上限のバイト数で切り捨てる（UTF-8の文字境界で切る）。切り捨てた場合はtrue
+fn truncate_payload(payload: &mut Option<String>, limit: usize) -> bool {
+    let Some(payload) = payload.as_mut().filter(|payload| payload.len() > limit) else {
+        return false;
+    };
+
+    let mut end = limit;
+    while !payload.is_char_boundary(end) {
+        end -= 1;
+    }
+    payload.truncate(end);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_large_payloads_are_truncated() {
+        let body = "あ".repeat(MAX_LOGGED_PAYLOAD_BYTES);
+        let logger = Logger::new();
+        logger.record(
+            LogEntry::new(new_request_id(), "POST".to_string(), "/".to_string(), Vec::new(), None)
+                .with_body(&body)
+                .with_response("{}"),
+        );
+        let entry = logger.get_logs().remove(0);
+
+        assert!(entry.body_truncated);
+        assert_eq!(entry.body_size, Some(body.len()));
+        assert!(entry.body.unwrap().len() <= MAX_LOGGED_PAYLOAD_BYTES);
+        assert!(!entry.response_truncated);
+        assert_eq!(entry.response.as_deref(), Some("{}"));
+    }
+
+    #[test]
+    fn test_batch_items_are_recorded_in_order() {
+        let request: Request = serde_json::from_str(
+            r#"[{"jsonrpc":"2.0","method":"echo","params":["a"],"id":1},{"jsonrpc":"2.0","method":"missing","id":2}]"#,
+        )
+        .unwrap();
+        let Request::Batch(calls) = &request else {
+            panic!("expected a batch");
+        };
+
+        let batch = BatchLog::default();
+        let indexes: Vec<usize> = calls.iter().map(|call| batch.start(call)).collect();
+
+        // 完了の順序が入れ替わっても位置で対応付ける
+        let failure: Output = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":2}"#,
+        )
+        .unwrap();
+        batch.finish(indexes[1], Some(&failure), Duration::from_millis(2));
+        let success: Output = serde_json::from_str(r#"{"jsonrpc":"2.0","result":"a","id":1}"#).unwrap();
+        batch.finish(indexes[0], Some(&success), Duration::from_millis(1));
+
+        let entry = LogEntry::new(new_request_id(), "POST".to_string(), "/".to_string(), Vec::new(), None)
+            .with_call(&request)
+            .with_batch(&batch);
+        assert_eq!(entry.rpc_method, None);
+        assert_eq!(entry.items.len(), 2);
+        assert_eq!(entry.items[0].rpc_method.as_deref(), Some("echo"));
+        assert_eq!(entry.items[0].params.as_deref(), Some(r#"["a"]"#));
+        assert_eq!(entry.items[0].result.as_deref(), Some(r#""a""#));
+        assert_eq!(entry.items[1].error.as_ref().map(|error| error.code.code()), Some(-32601));
+        assert_eq!(entry.items[1].elapsed_ms, Some(2));
+    }
+
+    #[test]
+    fn test_log_file_rotation_and_history() {
+        let dir = std::env::temp_dir().join(format!("logs-test-{}", Uuid::new_v4()));
+        let config = ServerConfig {
+            log_persist: true,
+            log_memory_entries: 2,
+            log_max_file_bytes: 1,
+            log_retention_files: 2,
+            ..ServerConfig::default()
+        };
+        let logger = Logger::new();
+        logger.configure(&config, Some(&dir));
+
+        for _ in 0..5 {
+            logger.record(LogEntry::new(new_request_id(), "POST".to_string(), "/".to_string(), Vec::new(), None));
+        }
+
+        // 1件ごとにローテーションされ、書き込み中のファイル（と作り始めた時刻）と2つのローテーション済みファイルが残る
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 4);
+        assert_eq!(rotated_files(&dir).unwrap().len(), 2);
+        assert_eq!(logger.get_logs().len(), 2);
+
+        let query = LogQuery {
+            order: Some(LogOrder::OldestFirst),
+            limit: Some(2),
+            ..LogQuery::default()
+        };
+        let page = logger.query(&query).unwrap();
+        assert_eq!(page.entries.len(), 2);
+        assert_eq!(page.next_offset, Some(2));
+        let page = logger.query(&LogQuery { offset: Some(2), ..query }).unwrap();
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.next_offset, None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_file_age_does_not_depend_on_file_times() {
+        let dir = std::env::temp_dir().join(format!("logs-test-{}", Uuid::new_v4()));
+        let config = ServerConfig {
+            log_persist: true,
+            log_max_file_age_secs: Some(60),
+            ..ServerConfig::default()
+        };
+        let entry = || LogEntry::new(new_request_id(), "POST".to_string(), "/".to_string(), Vec::new(), None);
+
+        let mut file = LogFile::new(dir.clone(), &config);
+        file.append(&entry()).unwrap();
+        file.append(&entry()).unwrap();
+        assert!(rotated_files(&dir).unwrap().is_empty());
+
+        // 2分前に作り始めたファイル（作成時刻がなく、更新時刻は直前の書き込みの時刻）を開き直すとローテーションされる
+        let two_minutes_ago = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() - 120;
+        fs::write(dir.join(LOG_OPENED_FILE_NAME), two_minutes_ago.to_string()).unwrap();
+        let mut file = LogFile::new(dir.clone(), &config);
+        file.append(&entry()).unwrap();
+        assert_eq!(rotated_files(&dir).unwrap().len(), 1);
+
+        // ローテーション後のファイルは作り始めた時刻が記録し直される
+        file.append(&entry()).unwrap();
+        assert_eq!(rotated_files(&dir).unwrap().len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_persisted_logs_are_read_newest_first() {
+        let dir = std::env::temp_dir().join(format!("logs-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let write = |name: String, entries: &[(&str, u64)]| {
+            let lines: Vec<String> = entries
+                .iter()
+                .map(|(request_id, timestamp)| {
+                    let mut entry = LogEntry::new(request_id.to_string(), "POST".to_string(), "/".to_string(), Vec::new(), None);
+                    entry.timestamp = *timestamp;
+                    serde_json::to_string(&entry).unwrap() + "\n"
+                })
+                .collect();
+            fs::write(dir.join(name), lines.concat()).unwrap();
+        };
+        // ファイル名の時刻（1000秒）より後のエントリは、ファイルごと読み飛ばされたことの確認用
+        write(format!("{}{:013}{}", ROTATED_FILE_PREFIX, 1_000_000, ROTATED_FILE_SUFFIX), &[("a", 900), ("b", 5000)]);
+        write(format!("{}{:013}{}", ROTATED_FILE_PREFIX, 2_000_000, ROTATED_FILE_SUFFIX), &[("c", 1500), ("d", 1600)]);
+        write(LOG_FILE_NAME.to_string(), &[("e", 2500)]);
+
+        let logger = Logger::new();
+        let config = ServerConfig {
+            log_persist: true,
+            ..ServerConfig::default()
+        };
+        logger.configure(&config, Some(&dir));
+
+        let ids = |page: LogPage| page.entries.into_iter().map(|entry| entry.request_id).collect::<Vec<_>>();
+        let page = logger.query(&LogQuery { limit: Some(2), ..LogQuery::default() }).unwrap();
+        assert_eq!(page.next_offset, Some(2));
+        assert_eq!(ids(page), ["e", "d"]);
+
+        let page = logger.query(&LogQuery { from: Some(1200), ..LogQuery::default() }).unwrap();
+        assert_eq!(ids(page), ["e", "d", "c"]);
+
+        assert!(logger.find("a").unwrap().is_some());
+        assert!(logger.find("z").unwrap().is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_notifications_are_coalesced() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let logger = Logger::new();
+        let received = events.clone();
+        logger.set_listener(Box::new(move |event| received.lock().unwrap().push(event)));
+
+        // 開始と完了が同じ間隔内の場合は完了したエントリだけが通知される
+        let entry = LogEntry::new(new_request_id(), "POST".to_string(), "/".to_string(), Vec::new(), None);
+        logger.started(&entry);
+        logger.record(entry.with_status(200));
+        logger.record(LogEntry::new(new_request_id(), "IPC".to_string(), String::new(), Vec::new(), None));
+        logger.notifier.flush();
+        logger.clear_logs();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        let LogEvent::Appended(entries) = &events[0] else {
+            panic!("expected appended entries");
+        };
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].status, Some(200));
+        assert!(matches!(events[1], LogEvent::Cleared));
+    }
+
+    #[test]
+    fn test_notifications_are_sent_by_the_notifier_thread() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let logger = Logger::new();
+        let received = events.clone();
+        logger.set_listener(Box::new(move |event| received.lock().unwrap().push(event)));
+
+        // 間隔ごとに同じ通知スレッドがまとめて通知する
+        for expected in 1..=2 {
+            logger.record(LogEntry::new(new_request_id(), "IPC".to_string(), String::new(), Vec::new(), None));
+            logger.record(LogEntry::new(new_request_id(), "IPC".to_string(), String::new(), Vec::new(), None));
+            thread::sleep(NOTIFY_INTERVAL * 3);
+            assert_eq!(events.lock().unwrap().len(), expected);
+        }
+        let events = events.lock().unwrap();
+        assert!(events.iter().all(|event| matches!(event, LogEvent::Appended(entries) if entries.len() == 2)));
+    }
+
+    #[test]
+    fn test_completed_entries_are_not_dropped() {
+        let logger = Logger::new();
+        let completed = logger.subscribe_completed();
+
+        // 開始したリクエストは送らず、メモリに保持する件数を超えても完了したエントリはすべて届く
+        let entry = LogEntry::new(new_request_id(), "POST".to_string(), "/".to_string(), Vec::new(), None);
+        logger.started(&entry);
+        logger.record(entry.clone().with_status(200));
+        for _ in 0..DEFAULT_LOG_MEMORY_ENTRIES * 2 {
+            logger.record(LogEntry::new(new_request_id(), "IPC".to_string(), String::new(), Vec::new(), None));
+        }
+
+        let received: Vec<LogEntry> = completed.try_iter().collect();
+        assert_eq!(received.len(), DEFAULT_LOG_MEMORY_ENTRIES * 2 + 1);
+        assert_eq!(received[0].request_id, entry.request_id);
+        assert_eq!(received[0].status, Some(200));
+
+        // 受け取り側を閉じた後も記録は続けられる
+        drop(completed);
+        logger.record(LogEntry::new(new_request_id(), "IPC".to_string(), String::new(), Vec::new(), None));
+        assert!(logger.completed.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let logger = Logger::new();
+        logger.record(
+            LogEntry::new(new_request_id(), "POST".to_string(), "/".to_string(), Vec::new(), Some("ci".to_string()))
+                .with_body(r#"{"jsonrpc":"2.0","method":"notes_get","params":["Flaky"],"id":1}"#)
+                .with_response(r#"{"jsonrpc":"2.0","error":{"code":-32002,"message":"note not found"},"id":1}"#)
+                .with_status(200),
+        );
+        logger.record(
+            LogEntry::new(new_request_id(), "GET".to_string(), "/".to_string(), Vec::new(), None)
+                .with_status(405),
+        );
+
+        let page = |query: LogQuery| logger.query(&query).unwrap().entries;
+        assert_eq!(page(LogQuery::default()).len(), 2);
+        // 新しい順がデフォルト
+        assert_eq!(page(LogQuery::default())[0].method, "GET");
+        assert_eq!(page(LogQuery { method: Some("post".to_string()), ..LogQuery::default() }).len(), 1);
+        assert_eq!(page(LogQuery { status: Some(405), ..LogQuery::default() }).len(), 1);
+        assert_eq!(page(LogQuery { error_code: Some(-32002), ..LogQuery::default() }).len(), 1);
+        assert_eq!(page(LogQuery { text: Some("flaky".to_string()), ..LogQuery::default() }).len(), 1);
+        assert_eq!(page(LogQuery { token_id: Some("ci".to_string()), ..LogQuery::default() }).len(), 1);
+        assert!(page(LogQuery { from: Some(u64::MAX), ..LogQuery::default() }).is_empty());
+    }
+
+    #[test]
+    fn test_legacy_headers_are_parsed() {
+        let entry: LogEntry = serde_json::from_str(
+            r#"{"request_id":"a","timestamp":0,"method":"POST","uri":"/","headers":"host: \"127.0.0.1:3030\", content-type: \"application/json\", ","body":null,"response":null,"status":null,"token_id":null,"rpc_id":null,"rpc_method":null,"body_size":null,"response_size":null,"body_truncated":false,"response_truncated":false,"elapsed_ms":null}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            entry.headers,
+            vec![
+                LogHeader::new("host", "127.0.0.1:3030"),
+                LogHeader::new("content-type", "application/json"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sanitize_request_id() {
+        assert_eq!(sanitize_request_id(" abc-123 "), Some("abc-123".to_string()));
+        assert_eq!(sanitize_request_id(""), None);
+        assert_eq!(sanitize_request_id("a\r\nb"), None);
+        assert_eq!(sanitize_request_id(&"a".repeat(MAX_REQUEST_ID_LEN + 1)), None);
+    }
+}
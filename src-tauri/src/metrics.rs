@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// レイテンシのヒストグラムのバケット（秒）
+const LATENCY_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+// 呼び出しの結果
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    Success,
+    Error,
+}
+
+// メソッドごとの呼び出し数とレイテンシ
+#[derive(Default)]
+struct MethodMetrics {
+    success: u64,
+    error: u64,
+    // LATENCY_BUCKETS の各バケット以下だった呼び出しの数（累積ではない）
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    latency_sum: f64,
+}
+
+// GET /metrics で公開するサーバーのメトリクス（すべてのトランスポートで共有）
+#[derive(Default)]
+pub struct Metrics {
+    methods: Mutex<BTreeMap<String, MethodMetrics>>,
+    in_flight: AtomicUsize,
+    // HTTPリクエストを制限により拒否した回数（理由ごと）
+    rejections: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+// 取得時に読み取る値
+pub struct Gauges {
+    // NotesState が管理されていない場合はNone
+    pub notes: Option<usize>,
+    pub log_entries: usize,
+}
+
+impl Metrics {
+    // JSON-RPCの呼び出し1件を記録
+    pub fn observe_call(&self, method: &str, outcome: Outcome, elapsed: Duration) {
+        let mut methods = self.methods.lock().unwrap();
+        let metrics = methods.entry(method.to_string()).or_default();
+        match outcome {
+            Outcome::Success => metrics.success += 1,
+            Outcome::Error => metrics.error += 1,
+        }
+
+        let seconds = elapsed.as_secs_f64();
+        if let Some(index) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            metrics.buckets[index] += 1;
+        }
+        metrics.latency_sum += seconds;
+    }
+
+    // 制限によりHTTPリクエストを拒否したことを記録
+    pub fn observe_rejection(&self, reason: &'static str) {
+        *self.rejections.lock().unwrap().entry(reason).or_default() += 1;
+    }
+
+    // 処理中のリクエストとして数える（ガードを破棄すると処理中から外れる）
+    pub fn start_request(self: &Arc<Self>) -> InFlightGuard {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard(self.clone())
+    }
+
+    // Prometheusのテキスト形式で出力
+    pub fn render(&self, gauges: &Gauges) -> String {
+        let methods = self.methods.lock().unwrap();
+        let mut out = String::new();
+
+        out.push_str("# HELP rpc_requests_total JSON-RPC calls by method and outcome.\n");
+        out.push_str("# TYPE rpc_requests_total counter\n");
+        for (method, metrics) in methods.iter() {
+            for (outcome, count) in [("success", metrics.success), ("error", metrics.error)] {
+                let _ = writeln!(out, "rpc_requests_total{{method=\"{}\",outcome=\"{}\"}} {}", method, outcome, count);
+            }
+        }
+
+        out.push_str("# HELP rpc_request_duration_seconds JSON-RPC call latency by method.\n");
+        out.push_str("# TYPE rpc_request_duration_seconds histogram\n");
+        for (method, metrics) in methods.iter() {
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(metrics.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "rpc_request_duration_seconds_bucket{{method=\"{}\",le=\"{}\"}} {}",
+                    method, bound, cumulative
+                );
+            }
+            let total = metrics.success + metrics.error;
+            let _ = writeln!(out, "rpc_request_duration_seconds_bucket{{method=\"{}\",le=\"+Inf\"}} {}", method, total);
+            let _ = writeln!(out, "rpc_request_duration_seconds_sum{{method=\"{}\"}} {}", method, metrics.latency_sum);
+            let _ = writeln!(out, "rpc_request_duration_seconds_count{{method=\"{}\"}} {}", method, total);
+        }
+
+        out.push_str("# HELP rpc_http_rejected_total HTTP requests rejected by limits, by reason.\n");
+        out.push_str("# TYPE rpc_http_rejected_total counter\n");
+        for (reason, count) in self.rejections.lock().unwrap().iter() {
+            let _ = writeln!(out, "rpc_http_rejected_total{{reason=\"{}\"}} {}", reason, count);
+        }
+
+        out.push_str("# HELP rpc_requests_in_flight JSON-RPC requests being processed.\n");
+        out.push_str("# TYPE rpc_requests_in_flight gauge\n");
+        let _ = writeln!(out, "rpc_requests_in_flight {}", self.in_flight.load(Ordering::Relaxed));
+
+        if let Some(notes) = gauges.notes {
+            out.push_str("# HELP rpc_notes Number of stored notes.\n");
+            out.push_str("# TYPE rpc_notes gauge\n");
+            let _ = writeln!(out, "rpc_notes {}", notes);
+        }
+
+        out.push_str("# HELP rpc_log_buffer_entries Request log entries held in memory.\n");
+        out.push_str("# TYPE rpc_log_buffer_entries gauge\n");
+        let _ = writeln!(out, "rpc_log_buffer_entries {}", gauges.log_entries);
+
+        out
+    }
+}
+
+// 処理中のリクエストのガード
+pub struct InFlightGuard(Arc<Metrics>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_prometheus_text() {
+        let metrics = Arc::new(Metrics::default());
+        metrics.observe_call("echo", Outcome::Success, Duration::from_millis(3));
+        metrics.observe_call("echo", Outcome::Error, Duration::from_secs(10));
+        let guard = metrics.start_request();
+        metrics.observe_rejection("rate_limited");
+
+        let text = metrics.render(&Gauges { notes: Some(2), log_entries: 5 });
+        assert!(text.contains("rpc_requests_total{method=\"echo\",outcome=\"success\"} 1\n"));
+        assert!(text.contains("rpc_requests_total{method=\"echo\",outcome=\"error\"} 1\n"));
+        assert!(text.contains("rpc_request_duration_seconds_bucket{method=\"echo\",le=\"0.005\"} 1\n"));
+        assert!(text.contains("rpc_request_duration_seconds_bucket{method=\"echo\",le=\"5\"} 1\n"));
+        assert!(text.contains("rpc_request_duration_seconds_bucket{method=\"echo\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("rpc_requests_in_flight 1\n"));
+        assert!(text.contains("rpc_http_rejected_total{reason=\"rate_limited\"} 1\n"));
+        assert!(text.contains("rpc_notes 2\n"));
+        assert!(text.contains("rpc_log_buffer_entries 5\n"));
+
+        drop(guard);
+        let text = metrics.render(&Gauges { notes: None, log_entries: 0 });
+        assert!(text.contains("rpc_requests_in_flight 0\n"));
+        assert!(!text.contains("rpc_notes"));
+    }
+}
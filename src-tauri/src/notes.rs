@@ -1,33 +1,67 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::{Manager, AppHandle, Emitter}; // Emitter トレイトを追加
 use uuid::Uuid;
 
+use crate::error::AppError;
+
 // メモのデータ構造
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Note {
     pub id: String,
     pub title: String,
     pub content: String,
     pub created_at: u64,
     pub updated_at: u64,
+    // 更新のたびに増える版番号（競合の検出に使用）
+    #[serde(default)]
+    pub revision: u64,
+}
+
+// メモの変更の種類
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteChangeKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+// メモ変更の通知内容（notes-changed イベントと購読者への通知で使用）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct NoteChange {
+    pub id: String,
+    pub kind: NoteChangeKind,
 }
 
+// メモ変更時に呼び出されるリスナー
+pub type NoteChangeListener = Box<dyn Fn(&NoteChange) + Send + Sync>;
+
 // メモの保管庫
-#[derive(Debug)]
 pub struct NoteStore {
     notes: Mutex<HashMap<String, Note>>,
     file_path: PathBuf,
-    app_handle: AppHandle, // AppHandleを保持
+    app_handle: Option<AppHandle>, // AppHandleを保持（ない場合はイベントを発行しない）
+    // Webview以外（WebSocketの購読者など）へ変更を伝えるリスナー
+    listeners: Mutex<HashMap<u64, NoteChangeListener>>,
+    next_listener_id: AtomicU64,
 }
 
 // イベント名の定数
 const EVENT_NOTES_CHANGED: &str = "notes-changed";
 
+// エラーで表示するリソース名
+const RESOURCE_NOTE: &str = "note";
+
+// 書き込み確認に使う一時ファイル名の接頭辞（同時に確認しても衝突しないよう呼び出しごとに一意の名前を付ける）
+const WRITE_CHECK_FILE_PREFIX: &str = ".write-check-";
+
 impl NoteStore {
     // 新しいメモ保管庫を作成
     pub fn new(app_handle: &tauri::AppHandle) -> Result<Self, Box<dyn std::error::Error>> {
@@ -35,14 +69,21 @@ impl NoteStore {
         // Tauri 2.0では Manager トレイトを通じてパス関連のAPIにアクセスする
         // app_data_dir()はResult<PathBuf, Error>を返すので、ok_or()は必要ない
         let app_data_dir = app_handle.path().app_data_dir()?;
-        
+
+        let mut store = Self::open(&app_data_dir)?;
+        store.app_handle = Some(app_handle.clone());
+        Ok(store)
+    }
+
+    // 指定したディレクトリのメモ保管庫を開く（AppHandleを使わないためTauriイベントは発行しない）
+    pub fn open(data_dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
         // データディレクトリが存在しない場合は作成
-        if !app_data_dir.exists() {
-            fs::create_dir_all(&app_data_dir)?;
+        if !data_dir.exists() {
+            fs::create_dir_all(data_dir)?;
         }
         
         // メモを保存するJSONファイルのパス
-        let file_path = app_data_dir.join("notes.json");
+        let file_path = data_dir.join("notes.json");
         
         // ファイルが存在する場合は読み込み、存在しない場合は空のハッシュマップを作成
         let notes = if file_path.exists() {
@@ -56,7 +97,9 @@ impl NoteStore {
         Ok(Self {
             notes: Mutex::new(notes),
             file_path,
-            app_handle: app_handle.clone(),
+            app_handle: None,
+            listeners: Mutex::new(HashMap::new()),
+            next_listener_id: AtomicU64::new(1),
         })
     }
     
@@ -66,6 +109,23 @@ impl NoteStore {
         notes.values().cloned().collect()
     }
     
+    // メモの件数
+    pub fn count(&self) -> usize {
+        self.notes.lock().unwrap().len()
+    }
+
+    // 保存先のディレクトリに書き込めるか確認（一時ファイルを作成して削除する）
+    pub fn check_writable(&self) -> Result<(), AppError> {
+        let dir = self
+            .file_path
+            .parent()
+            .ok_or_else(|| AppError::storage("Note file has no parent directory"))?;
+        let probe = dir.join(format!("{}{}", WRITE_CHECK_FILE_PREFIX, Uuid::new_v4().simple()));
+        fs::OpenOptions::new().write(true).create_new(true).open(&probe)?;
+        fs::remove_file(&probe)?;
+        Ok(())
+    }
+
     // 特定のメモを取得
     pub fn get(&self, id: &str) -> Option<Note> {
         let notes = self.notes.lock().unwrap();
@@ -73,7 +133,9 @@ impl NoteStore {
     }
     
     // 新規メモ作成
-    pub fn create(&self, title: String, content: String) -> Result<Note, Box<dyn std::error::Error>> {
+    pub fn create(&self, title: String, content: String) -> Result<Note, AppError> {
+        validate_title(&title)?;
+
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap_or_default()
@@ -85,87 +147,181 @@ impl NoteStore {
             content,
             created_at: now,
             updated_at: now,
+            revision: 1,
         };
         
         {
-            let mut notes = self.notes.lock().unwrap();
+            // ファイルに保存できた場合のみメモリに反映する
+            let mut notes = self.lock_notes()?;
+            self.save_to_file(notes.values().chain(std::iter::once(&note)))?;
             notes.insert(note.id.clone(), note.clone());
         }
         
-        self.save_to_file()?;
-        
         // メモ変更イベントを発行
-        self.emit_notes_changed_event();
+        self.emit_notes_changed_event(&note.id, NoteChangeKind::Created);
         
         Ok(note)
     }
     
-    // メモ更新
-    pub fn update(&self, id: &str, title: Option<String>, content: Option<String>) -> Result<Option<Note>, Box<dyn std::error::Error>> {
-        let mut updated_note = None;
-        
-        {
-            let mut notes = self.notes.lock().unwrap();
-            
-            if let Some(note) = notes.get_mut(id) {
-                if let Some(title) = title {
-                    note.title = title;
-                }
-                
-                if let Some(content) = content {
-                    note.content = content;
+    // メモ更新（expected_revision を指定した場合は版番号が一致するときだけ更新する）
+    pub fn update(&self, id: &str, title: Option<String>, content: Option<String>, expected_revision: Option<u64>) -> Result<Note, AppError> {
+        if let Some(title) = &title {
+            validate_title(title)?;
+        }
+
+        let updated_note = {
+            let mut notes = self.lock_notes()?;
+            let mut note = notes
+                .get(id)
+                .cloned()
+                .ok_or_else(|| AppError::not_found(RESOURCE_NOTE, id))?;
+
+            if let Some(expected) = expected_revision {
+                if expected != note.revision {
+                    return Err(AppError::Conflict {
+                        id: id.to_string(),
+                        expected,
+                        actual: note.revision,
+                    });
                 }
-                
-                note.updated_at = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs();
-                
-                updated_note = Some(note.clone());
             }
-        }
-        
-        if updated_note.is_some() {
-            self.save_to_file()?;
+
+            if let Some(title) = title {
+                note.title = title;
+            }
             
-            // メモ変更イベントを発行
-            self.emit_notes_changed_event();
-        }
+            if let Some(content) = content {
+                note.content = content;
+            }
+            
+            note.updated_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            note.revision += 1;
+
+            // ファイルに保存できた場合のみメモリに反映する
+            self.save_to_file(notes.values().filter(|other| other.id != id).chain(std::iter::once(&note)))?;
+            notes.insert(id.to_string(), note.clone());
+            note
+        };
+        
+        // メモ変更イベントを発行
+        self.emit_notes_changed_event(id, NoteChangeKind::Updated);
         
         Ok(updated_note)
     }
     
     // メモ削除
-    pub fn delete(&self, id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    pub fn delete(&self, id: &str) -> Result<bool, AppError> {
         let removed = {
-            let mut notes = self.notes.lock().unwrap();
-            notes.remove(id).is_some()
+            let mut notes = self.lock_notes()?;
+            let exists = notes.contains_key(id);
+            if exists {
+                // ファイルに保存できた場合のみメモリから削除する
+                self.save_to_file(notes.values().filter(|other| other.id != id))?;
+                notes.remove(id);
+            }
+            exists
         };
         
         if removed {
-            self.save_to_file()?;
-            
             // メモ変更イベントを発行
-            self.emit_notes_changed_event();
+            self.emit_notes_changed_event(id, NoteChangeKind::Deleted);
         }
         
         Ok(removed)
     }
     
-    // ファイルに保存
-    fn save_to_file(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let notes = self.notes.lock().unwrap();
-        let notes_vec: Vec<Note> = notes.values().cloned().collect();
+    // メモ変更リスナーを登録し、解除用のIDを返す
+    pub fn add_change_listener(&self, listener: NoteChangeListener) -> u64 {
+        let id = self.next_listener_id.fetch_add(1, Ordering::Relaxed);
+        self.listeners.lock().unwrap().insert(id, listener);
+        id
+    }
+
+    // メモ変更リスナーを解除
+    pub fn remove_change_listener(&self, id: u64) {
+        self.listeners.lock().unwrap().remove(&id);
+    }
+    
+    // 更新系の操作で使うロック（ポイズンした場合はエラーを返す）
+    fn lock_notes(&self) -> Result<MutexGuard<'_, HashMap<String, Note>>, AppError> {
+        self.notes
+            .lock()
+            .map_err(|_| AppError::internal("Note store lock is poisoned"))
+    }
+
+    // 変更後のメモをファイルに保存（メモリに反映する前に呼び出し、ロックを保持したまま書き込む）
+    fn save_to_file<'a>(&self, notes: impl Iterator<Item = &'a Note>) -> Result<(), AppError> {
+        let notes_vec: Vec<&Note> = notes.collect();
         let json = serde_json::to_string_pretty(&notes_vec)?;
         fs::write(&self.file_path, json)?;
         Ok(())
     }
     
     // メモ変更イベントを発行する関数
-    fn emit_notes_changed_event(&self) {
+    fn emit_notes_changed_event(&self, id: &str, kind: NoteChangeKind) {
+        let change = NoteChange {
+            id: id.to_string(),
+            kind,
+        };
+
         // Tauri 2.0では emit_all が emit に変更されています
-        if let Err(e) = self.app_handle.emit(EVENT_NOTES_CHANGED, ()) {
-            eprintln!("Failed to emit notes-changed event: {}", e);
+        if let Some(app_handle) = &self.app_handle {
+            if let Err(e) = app_handle.emit(EVENT_NOTES_CHANGED, change.clone()) {
+                eprintln!("Failed to emit notes-changed event: {}", e);
+            }
+        }
+
+        // 登録されたリスナーに通知
+        for listener in self.listeners.lock().unwrap().values() {
+            listener(&change);
         }
     }
-}
\ No newline at end of file
+}
+// タイトルは空にできない
+fn validate_title(title: &str) -> Result<(), AppError> {
+    if title.trim().is_empty() {
+        return Err(AppError::validation(Some("title"), "Title must not be empty"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_save_does_not_change_notes() {
+        let dir = std::env::temp_dir().join(format!("notes-test-{}", Uuid::new_v4()));
+        let store = NoteStore::open(&dir).unwrap();
+        let note = store.create("title".to_string(), "content".to_string()).unwrap();
+
+        // 保存先に書き込めない状態にする（root でも書き込みに失敗するよう、ファイルの位置にディレクトリを置く）
+        let file_path = dir.join("notes.json");
+        fs::remove_file(&file_path).unwrap();
+        fs::create_dir(&file_path).unwrap();
+
+        assert!(matches!(store.create("new".to_string(), String::new()), Err(AppError::Storage(_))));
+        assert_eq!(store.count(), 1);
+        assert!(matches!(
+            store.update(&note.id, Some("changed".to_string()), None, None),
+            Err(AppError::Storage(_))
+        ));
+        let unchanged = store.get(&note.id).unwrap();
+        assert_eq!((unchanged.title.as_str(), unchanged.revision), ("title", 1));
+        assert!(matches!(store.delete(&note.id), Err(AppError::Storage(_))));
+        assert!(store.get(&note.id).is_some());
+
+        // 書き込めるようになれば、失敗した変更を含まずに保存される
+        fs::remove_dir(&file_path).unwrap();
+        let updated = store.update(&note.id, None, Some("updated".to_string()), Some(1)).unwrap();
+        assert_eq!(updated.revision, 2);
+        let reopened = NoteStore::open(&dir).unwrap();
+        assert_eq!(reopened.list().len(), 1);
+        assert_eq!(reopened.get(&note.id).unwrap().title, "title");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,152 @@
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::JsonSchema;
+use serde_json::{json, Value};
+
+use crate::config::ServerConfig;
+use crate::error::error_description;
+use crate::notes::NoteChange;
+use crate::params::RpcParams;
+use crate::rpc::{subscriptions_enabled, METHODS, NOTIFICATION_NOTES_CHANGED, SUBSCRIBE_METHOD, UNSUBSCRIBE_METHOD};
+
+// OpenRPCドキュメントを返す組み込みメソッド
+pub const DISCOVER_METHOD: &str = "rpc.discover";
+
+// OpenRPCドキュメントを返すHTTPのパス
+pub const OPENRPC_PATH: &str = "/openrpc.json";
+
+const OPENRPC_VERSION: &str = "1.2.6";
+
+// すべてのメソッドが返しうるエラー（パラメータ不正、スコープ外）
+const COMMON_ERROR_CODES: &[i64] = &[-32602, crate::error::ERROR_CODE_FORBIDDEN];
+
+// パラメータのスキーマを生成する関数の型
+pub type ParamsSchema = fn(&mut SchemaGenerator) -> Vec<Value>;
+
+// 戻り値のスキーマを生成する関数の型
+pub type ResultSchema = fn(&mut SchemaGenerator) -> Value;
+
+// パラメータ構造体の各フィールドをOpenRPCのContent Descriptorに変換（FIELDS の順序）
+pub fn param_schemas<T: RpcParams + JsonSchema>(gen: &mut SchemaGenerator) -> Vec<Value> {
+    let schema = serde_json::to_value(T::json_schema(gen)).unwrap_or_default();
+
+    T::FIELDS
+        .iter()
+        .map(|field| {
+            json!({
+                "name": field.name,
+                "required": field.required,
+                "schema": schema["properties"][field.name].clone(),
+            })
+        })
+        .collect()
+}
+
+// 戻り値の型のスキーマ（構造体は components.schemas への参照になる）
+pub fn result_schema<T: JsonSchema>(gen: &mut SchemaGenerator) -> Value {
+    serde_json::to_value(gen.subschema_for::<T>()).unwrap_or_default()
+}
+
+// サーバー設定に応じたOpenRPCドキュメントを生成
+pub fn document(config: &ServerConfig) -> Value {
+    let mut gen = SchemaSettings::draft07()
+        .with(|settings| settings.definitions_path = "#/components/schemas/".to_string())
+        .into_generator();
+
+    let mut methods: Vec<Value> = METHODS
+        .iter()
+        .map(|method| {
+            let params = (method.params)(&mut gen);
+            let result = (method.result)(&mut gen);
+            json!({
+                "name": method.name,
+                "summary": method.summary,
+                "tags": [{ "name": method.scope }],
+                "paramStructure": "either",
+                "params": params,
+                "result": { "name": "result", "schema": result },
+                "errors": errors(method.errors),
+            })
+        })
+        .collect();
+
+    // 購読メソッドはWebSocket・IPCでのみ利用可能（位置引数のみ）
+    if subscriptions_enabled(config) {
+        let notification = result_schema::<NoteChange>(&mut gen);
+        methods.push(json!({
+            "name": SUBSCRIBE_METHOD,
+            "summary": "Subscribes to note changes (WebSocket and IPC only)",
+            "tags": [{ "name": "notes:read" }],
+            "paramStructure": "by-position",
+            "params": [],
+            "result": { "name": "subscription", "schema": result_schema::<String>(&mut gen) },
+            "errors": errors(&[]),
+            "x-notification": { "name": NOTIFICATION_NOTES_CHANGED, "schema": notification },
+        }));
+        methods.push(json!({
+            "name": UNSUBSCRIBE_METHOD,
+            "summary": "Cancels a note change subscription (WebSocket and IPC only)",
+            "tags": [{ "name": "notes:read" }],
+            "paramStructure": "by-position",
+            "params": [{
+                "name": "subscription",
+                "required": true,
+                "schema": result_schema::<String>(&mut gen),
+            }],
+            "result": { "name": "removed", "schema": result_schema::<bool>(&mut gen) },
+            "errors": errors(&[]),
+        }));
+    }
+
+    json!({
+        "openrpc": OPENRPC_VERSION,
+        "info": {
+            "title": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "methods": methods,
+        "components": {
+            "schemas": gen.take_definitions(),
+        },
+    })
+}
+
+// エラーコードをOpenRPCのError Objectに変換
+fn errors(codes: &[i64]) -> Vec<Value> {
+    COMMON_ERROR_CODES
+        .iter()
+        .chain(codes)
+        .map(|code| json!({ "code": code, "message": error_description(*code) }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_is_generated_from_method_table() {
+        let document = document(&ServerConfig {
+            ws_enabled: true,
+            ..ServerConfig::default()
+        });
+        let methods = document["methods"].as_array().unwrap();
+
+        // メソッドテーブルのすべてのメソッドと購読メソッドが含まれる
+        assert_eq!(methods.len(), METHODS.len() + 2);
+
+        let update = methods.iter().find(|method| method["name"] == "notes_update").unwrap();
+        let params = update["params"].as_array().unwrap();
+        let names: Vec<&str> = params.iter().map(|param| param["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["id", "title", "content", "expected_revision"]);
+        assert_eq!(params[0]["required"], true);
+        assert_eq!(params[1]["required"], false);
+        assert_eq!(update["result"]["schema"]["$ref"], "#/components/schemas/Note");
+        assert!(update["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|error| error["code"] == crate::error::ERROR_CODE_CONFLICT));
+
+        assert!(document["components"]["schemas"]["Note"]["properties"]["revision"].is_object());
+    }
+}
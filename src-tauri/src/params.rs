@@ -0,0 +1,252 @@
+use jsonrpc_core::{Error, ErrorCode, Params, Value};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map};
+
+// パラメータの型
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamType {
+    String,
+    // 0以上の整数
+    Integer,
+    // 負の値も取る整数
+    SignedInteger,
+    // 0以上、指定した値以下の整数
+    IntegerUpTo(u64),
+    // 指定したいずれかの文字列
+    OneOf(&'static [&'static str]),
+}
+
+impl ParamType {
+    // エラーメッセージに表示する型名
+    pub fn name(&self) -> String {
+        match self {
+            ParamType::String => "string".to_string(),
+            ParamType::Integer | ParamType::SignedInteger => "integer".to_string(),
+            ParamType::IntegerUpTo(max) => format!("integer between 0 and {}", max),
+            ParamType::OneOf(values) => format!("one of {}", values.join(", ")),
+        }
+    }
+
+    // JSONの型が一致するか（範囲や候補は見ない）
+    fn matches_type(&self, value: &Value) -> bool {
+        match self {
+            ParamType::String | ParamType::OneOf(_) => value.is_string(),
+            ParamType::Integer | ParamType::IntegerUpTo(_) => value.is_u64(),
+            ParamType::SignedInteger => value.is_i64(),
+        }
+    }
+
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            ParamType::IntegerUpTo(max) => value.as_u64().is_some_and(|n| n <= *max),
+            ParamType::OneOf(values) => value.as_str().is_some_and(|s| values.contains(&s)),
+            _ => self.matches_type(value),
+        }
+    }
+}
+
+// パラメータのフィールド定義
+#[derive(Clone, Copy, Debug)]
+pub struct ParamField {
+    pub name: &'static str,
+    pub ty: ParamType,
+    // 省略可能なフィールドは null も受け付ける
+    pub required: bool,
+}
+
+impl ParamField {
+    pub const fn required(name: &'static str, ty: ParamType) -> Self {
+        Self {
+            name,
+            ty,
+            required: true,
+        }
+    }
+
+    pub const fn optional(name: &'static str, ty: ParamType) -> Self {
+        Self {
+            name,
+            ty,
+            required: false,
+        }
+    }
+}
+
+// 位置引数（配列）と名前付き引数（オブジェクト）のどちらからでもデコードできるパラメータ
+pub trait RpcParams: DeserializeOwned {
+    // フィールドの定義（配列形式ではこの順序で値を割り当てる）
+    const FIELDS: &'static [ParamField];
+}
+
+// パラメータを取らないメソッド用
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+pub struct NoParams {}
+
+impl RpcParams for NoParams {
+    const FIELDS: &'static [ParamField] = &[];
+}
+
+// パラメータを型付きの構造体にデコード
+pub fn decode<T: RpcParams>(params: Params) -> Result<T, Error> {
+    let map = match params {
+        Params::Map(map) => map,
+        Params::Array(values) => {
+            if values.len() > T::FIELDS.len() {
+                return Err(invalid_params(
+                    format!(
+                        "Expected at most {} positional parameters, got {}",
+                        T::FIELDS.len(),
+                        values.len()
+                    ),
+                    None,
+                ));
+            }
+
+            T::FIELDS
+                .iter()
+                .zip(values)
+                .map(|(field, value)| (field.name.to_string(), value))
+                .collect()
+        }
+        Params::None => Map::new(),
+    };
+
+    // 定義されていないフィールドは拒否する
+    if let Some(unknown) = map
+        .keys()
+        .find(|key| !T::FIELDS.iter().any(|field| field.name == key.as_str()))
+    {
+        return Err(invalid_params(
+            format!("Unknown parameter '{}'", unknown),
+            Some(unknown),
+        ));
+    }
+
+    for field in T::FIELDS {
+        match map.get(field.name) {
+            None | Some(Value::Null) if field.required => {
+                return Err(field_error(
+                    field,
+                    format!(
+                        "Missing required parameter '{}' (expected {})",
+                        field.name,
+                        field.ty.name()
+                    ),
+                ));
+            }
+            None | Some(Value::Null) => {}
+            Some(value) if !field.ty.matches(value) => {
+                // 型は合っていて値が範囲外・候補外の場合は値そのものを示す
+                let actual = if field.ty.matches_type(value) {
+                    value.to_string()
+                } else {
+                    value_type_name(value).to_string()
+                };
+                return Err(field_error(
+                    field,
+                    format!(
+                        "Invalid parameter '{}': expected {}, got {}",
+                        field.name,
+                        field.ty.name(),
+                        actual
+                    ),
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+
+    serde_json::from_value(Value::Object(map)).map_err(|e| invalid_params(e.to_string(), None))
+}
+
+// フィールドを特定できるinvalid_paramsエラー
+fn field_error(field: &ParamField, message: String) -> Error {
+    let mut error = invalid_params(message, Some(field.name));
+    if let Some(Value::Object(data)) = error.data.as_mut() {
+        data.insert("expected".to_string(), Value::String(field.ty.name()));
+    }
+    error
+}
+
+fn invalid_params(message: String, field: Option<&str>) -> Error {
+    Error {
+        code: ErrorCode::InvalidParams,
+        message,
+        data: field.map(|field| json!({ "field": field })),
+    }
+}
+
+// エラーメッセージに表示するJSONの型名
+fn value_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestParams {
+        id: String,
+        title: Option<String>,
+    }
+
+    impl RpcParams for TestParams {
+        const FIELDS: &'static [ParamField] = &[
+            ParamField::required("id", ParamType::String),
+            ParamField::optional("title", ParamType::String),
+        ];
+    }
+
+    fn params(value: Value) -> Params {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_decode_positional_and_named() {
+        let expected = TestParams {
+            id: "a".to_string(),
+            title: Some("t".to_string()),
+        };
+        assert_eq!(
+            decode::<TestParams>(params(json!(["a", "t"]))).unwrap(),
+            expected
+        );
+        assert_eq!(
+            decode::<TestParams>(params(json!({ "id": "a", "title": "t" }))).unwrap(),
+            expected
+        );
+
+        let decoded = decode::<TestParams>(params(json!(["a"]))).unwrap();
+        assert_eq!(decoded.title, None);
+    }
+
+    #[test]
+    fn test_decode_errors_name_the_field() {
+        let error = decode::<TestParams>(params(json!({ "title": "t" }))).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidParams);
+        assert_eq!(
+            error.data,
+            Some(json!({ "field": "id", "expected": "string" }))
+        );
+
+        let error = decode::<TestParams>(params(json!([1]))).unwrap_err();
+        assert_eq!(
+            error.message,
+            "Invalid parameter 'id': expected string, got number"
+        );
+
+        let error = decode::<TestParams>(params(json!({ "id": "a", "extra": 1 }))).unwrap_err();
+        assert_eq!(error.message, "Unknown parameter 'extra'");
+
+        assert!(decode::<TestParams>(params(json!(["a", "t", "x"]))).is_err());
+    }
+}
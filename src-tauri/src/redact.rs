@@ -0,0 +1,241 @@
+use serde_json::Value;
+
+use crate::config::ServerConfig;
+use crate::logs::LogEntry;
+
+// 伏せた値の代わりに記録する文字列
+pub const REDACTED: &str = "[REDACTED]";
+
+// デフォルトで値を伏せるヘッダー（認証情報やセッションを含むもの）
+pub const DEFAULT_REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+];
+
+// URIのクエリに含まれる場合は常に値を伏せるパラメータ（WebSocketのトークン）
+const REDACTED_QUERY_PARAMS: &[&str] = &["access_token"];
+
+// ログに記録する前に秘匿情報を取り除く設定
+#[derive(Debug, Clone, Default)]
+pub struct Redaction {
+    // 小文字に揃えたヘッダー名
+    headers: Vec<String>,
+    // JSON Pointerをデコードしたトークンの列
+    fields: Vec<Vec<String>>,
+    metadata_only: bool,
+}
+
+impl Redaction {
+    pub fn new(config: &ServerConfig) -> Self {
+        Self {
+            headers: config.log_redact_headers.iter().map(|name| name.to_lowercase()).collect(),
+            fields: config.log_redact_fields.iter().map(|pointer| parse_pointer(pointer)).collect(),
+            metadata_only: config.log_metadata_only,
+        }
+    }
+
+    // ログエントリから秘匿情報を取り除く
+    pub fn apply(&self, entry: &mut LogEntry) {
+        for header in &mut entry.headers {
+            if self.headers.contains(&header.name.to_lowercase()) {
+                header.value = REDACTED.to_string();
+            }
+        }
+        entry.uri = redact_query(&entry.uri);
+
+        if self.metadata_only {
+            entry.body = None;
+            entry.response = None;
+            for item in &mut entry.items {
+                item.params = None;
+                item.result = None;
+                if let Some(error) = item.error.as_mut() {
+                    error.data = None;
+                }
+            }
+            return;
+        }
+
+        if self.fields.is_empty() {
+            return;
+        }
+
+        if let Some(body) = entry.body.as_mut() {
+            self.mask_payload(body, "params");
+        }
+        if let Some(response) = entry.response.as_mut() {
+            self.mask_payload(response, "result");
+        }
+        for item in &mut entry.items {
+            if let Some(params) = item.params.as_mut() {
+                self.mask_json(params);
+            }
+            if let Some(result) = item.result.as_mut() {
+                self.mask_json(result);
+            }
+        }
+    }
+
+    // JSON-RPCのリクエスト・レスポンス（バッチを含む）の params または result を伏せる
+    // JSONとして解釈できない本文はそのまま残す
+    fn mask_payload(&self, payload: &mut String, member: &str) {
+        let Ok(mut value) = serde_json::from_str::<Value>(payload) else {
+            return;
+        };
+
+        let mut changed = false;
+        match &mut value {
+            Value::Array(messages) => {
+                for message in messages {
+                    changed |= self.mask_member(message, member);
+                }
+            }
+            message => changed = self.mask_member(message, member),
+        }
+
+        if changed {
+            *payload = value.to_string();
+        }
+    }
+
+    fn mask_member(&self, message: &mut Value, member: &str) -> bool {
+        match message.get_mut(member) {
+            Some(value) => self.mask_value(value),
+            None => false,
+        }
+    }
+
+    // バッチの各呼び出しのパラメータ・結果（JSON文字列）を伏せる
+    fn mask_json(&self, json: &mut String) {
+        let Ok(mut value) = serde_json::from_str::<Value>(json) else {
+            return;
+        };
+        if self.mask_value(&mut value) {
+            *json = value.to_string();
+        }
+    }
+
+    fn mask_value(&self, value: &mut Value) -> bool {
+        self.fields
+            .iter()
+            .fold(false, |changed, tokens| mask_pointer(value, tokens) | changed)
+    }
+}
+
+// JSON Pointer（RFC 6901）をトークンに分割（"" は値全体を指す）
+fn parse_pointer(pointer: &str) -> Vec<String> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+// トークンの指す値を伏せる（"*" はオブジェクトと配列の任意の要素に一致）。伏せた場合はtrue
+fn mask_pointer(value: &mut Value, tokens: &[String]) -> bool {
+    let Some((token, rest)) = tokens.split_first() else {
+        *value = Value::String(REDACTED.to_string());
+        return true;
+    };
+
+    match value {
+        Value::Object(map) if token == "*" => map
+            .values_mut()
+            .fold(false, |changed, value| mask_pointer(value, rest) | changed),
+        Value::Object(map) => map.get_mut(token).is_some_and(|value| mask_pointer(value, rest)),
+        Value::Array(values) if token == "*" => values
+            .iter_mut()
+            .fold(false, |changed, value| mask_pointer(value, rest) | changed),
+        Value::Array(values) => token
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| values.get_mut(index))
+            .is_some_and(|value| mask_pointer(value, rest)),
+        _ => false,
+    }
+}
+
+// URIのクエリに含まれるトークンを伏せる
+fn redact_query(uri: &str) -> String {
+    let Some((path, query)) = uri.split_once('?') else {
+        return uri.to_string();
+    };
+
+    let query: Vec<String> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if REDACTED_QUERY_PARAMS.contains(&name) => format!("{}={}", name, REDACTED),
+            _ => pair.to_string(),
+        })
+        .collect();
+    format!("{}?{}", path, query.join("&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logs::LogHeader;
+
+    fn entry() -> LogEntry {
+        LogEntry::new(
+            "req-1".to_string(),
+            "POST".to_string(),
+            "/?access_token=secret&debug=1".to_string(),
+            vec![
+                LogHeader::new("Authorization", "Bearer secret"),
+                LogHeader::new("Content-Type", "application/json"),
+            ],
+            None,
+        )
+        .with_body(r#"{"jsonrpc":"2.0","method":"notes_create","params":{"title":"a","content":"secret"},"id":1}"#)
+        .with_response(r#"{"jsonrpc":"2.0","result":{"id":"n1","content":"secret"},"id":1}"#)
+    }
+
+    #[test]
+    fn test_headers_and_fields_are_redacted() {
+        let config = ServerConfig {
+            log_redact_fields: vec!["/content".to_string(), "/tags/*".to_string()],
+            ..ServerConfig::default()
+        };
+        let mut entry = entry();
+        Redaction::new(&config).apply(&mut entry);
+
+        assert_eq!(entry.headers[0].value, REDACTED);
+        assert_eq!(entry.headers[1].value, "application/json");
+        assert_eq!(entry.uri, "/?access_token=[REDACTED]&debug=1");
+
+        let body: Value = serde_json::from_str(entry.body.as_deref().unwrap()).unwrap();
+        assert_eq!(body["params"]["content"], REDACTED);
+        assert_eq!(body["params"]["title"], "a");
+        let response: Value = serde_json::from_str(entry.response.as_deref().unwrap()).unwrap();
+        assert_eq!(response["result"]["content"], REDACTED);
+        assert_eq!(response["result"]["id"], "n1");
+    }
+
+    #[test]
+    fn test_metadata_only_records_no_payloads() {
+        let config = ServerConfig {
+            log_metadata_only: true,
+            ..ServerConfig::default()
+        };
+        let mut entry = entry();
+        Redaction::new(&config).apply(&mut entry);
+
+        assert_eq!(entry.body, None);
+        assert_eq!(entry.response, None);
+        assert!(entry.body_size.is_some());
+    }
+
+    #[test]
+    fn test_mask_pointer() {
+        let mut value = serde_json::json!({ "notes": [{ "a/b": 1, "c": 2 }, { "a/b": 3 }] });
+        assert!(mask_pointer(&mut value, &parse_pointer("/notes/*/a~1b")));
+        assert_eq!(value["notes"][0]["a/b"], REDACTED);
+        assert_eq!(value["notes"][1]["a/b"], REDACTED);
+        assert_eq!(value["notes"][0]["c"], 2);
+        assert!(!mask_pointer(&mut value, &parse_pointer("/missing")));
+    }
+}
@@ -0,0 +1,194 @@
+use jsonrpc_core::{Call, Request};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::auth::AuthContext;
+use crate::error::AppError;
+use crate::logs::{new_request_id, LogEntry};
+use crate::redact::REDACTED;
+use crate::rpc::{is_mutating, RpcHandler, RpcMeta, Transport};
+
+// 比較結果に含める差分の最大数
+const MAX_DIFFERENCES: usize = 50;
+
+// replay_log_entry コマンドの戻り値
+#[derive(Serialize, Debug, Clone)]
+pub struct ReplayResult {
+    // 再実行のリクエストID（ログには replay_of に元のリクエストIDを持つエントリとして記録される）
+    pub request_id: String,
+    pub replay_of: String,
+    // 再実行で返されたレスポンス（通知のみの場合はNone）
+    pub response: Option<Value>,
+    // 元のリクエストで記録されていたレスポンス
+    pub original_response: Option<Value>,
+    // 元のレスポンスと一致したか（元のレスポンスが記録されていない場合はNone）
+    pub matches: Option<bool>,
+    // 値が異なる箇所のJSON Pointer（最大50件）
+    pub differences: Vec<String>,
+}
+
+// 記録されたJSON-RPCリクエストを同じハンドラーでプロセス内で再実行し、元のレスポンスと比較する
+// データを変更するメソッドを含む場合は confirm が必要
+pub fn replay(io: &RpcHandler, original: &LogEntry, confirm: bool) -> Result<ReplayResult, AppError> {
+    let body = original
+        .body
+        .as_deref()
+        .ok_or_else(|| AppError::validation(Some("request_id"), "Log entry has no recorded request body"))?;
+    if original.body_truncated {
+        return Err(AppError::validation(
+            Some("request_id"),
+            "Request body was truncated and cannot be replayed",
+        ));
+    }
+    if body.contains(REDACTED) {
+        return Err(AppError::validation(
+            Some("request_id"),
+            "Request body contains redacted values and cannot be replayed",
+        ));
+    }
+
+    let request: Request = serde_json::from_str(body)
+        .map_err(|_| AppError::validation(Some("request_id"), "Log entry is not a JSON-RPC request"))?;
+    if let Some(method) = call_methods(&request).find(|method| is_mutating(method)) {
+        if !confirm {
+            return Err(AppError::validation(
+                Some("confirm"),
+                format!("Replaying '{}' modifies data; set confirm to replay it", method),
+            ));
+        }
+    }
+
+    // Tauriコマンドからの呼び出しのため、すべてのメソッドを許可する
+    let request_id = new_request_id();
+    let meta = RpcMeta {
+        auth: AuthContext::full_access(None),
+        transport: Transport::Replay,
+        endpoint: Some(original.uri.clone()),
+        request_id: Some(request_id.clone()),
+        replay_of: Some(original.request_id.clone()),
+        ..RpcMeta::default()
+    };
+    let response = io
+        .handle_request_sync(body, meta)
+        .and_then(|response| serde_json::from_str::<Value>(&response).ok());
+
+    // 切り捨てられたレスポンスや空のレスポンスは比較しない
+    let original_response = original
+        .response
+        .as_deref()
+        .filter(|_| !original.response_truncated)
+        .and_then(|response| serde_json::from_str::<Value>(response).ok());
+
+    let mut differences = Vec::new();
+    let matches = original_response.as_ref().map(|original_response| {
+        let null = Value::Null;
+        diff(original_response, response.as_ref().unwrap_or(&null), String::new(), &mut differences);
+        differences.is_empty()
+    });
+
+    Ok(ReplayResult {
+        request_id,
+        replay_of: original.request_id.clone(),
+        response,
+        original_response,
+        matches,
+        differences,
+    })
+}
+
+// リクエストに含まれるメソッド名
+fn call_methods(request: &Request) -> impl Iterator<Item = &str> {
+    let calls = match request {
+        Request::Single(call) => std::slice::from_ref(call),
+        Request::Batch(calls) => calls.as_slice(),
+    };
+    calls.iter().filter_map(|call| match call {
+        Call::MethodCall(method_call) => Some(method_call.method.as_str()),
+        Call::Notification(notification) => Some(notification.method.as_str()),
+        Call::Invalid { .. } => None,
+    })
+}
+
+// 2つの値が異なる箇所をJSON Pointerで列挙
+fn diff(left: &Value, right: &Value, pointer: String, differences: &mut Vec<String>) {
+    if differences.len() >= MAX_DIFFERENCES {
+        return;
+    }
+
+    match (left, right) {
+        (Value::Object(left), Value::Object(right)) => {
+            let mut keys: Vec<&String> = left.keys().chain(right.keys().filter(|key| !left.contains_key(*key))).collect();
+            keys.sort();
+            // 片方にしかないキーは null と比較する
+            let null = Value::Null;
+            for key in keys {
+                let child = format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1"));
+                diff(left.get(key).unwrap_or(&null), right.get(key).unwrap_or(&null), child, differences);
+            }
+        }
+        (Value::Array(left), Value::Array(right)) if left.len() == right.len() => {
+            for (index, (l, r)) in left.iter().zip(right).enumerate() {
+                diff(l, r, format!("{}/{}", pointer, index), differences);
+            }
+        }
+        _ if left != right => differences.push(pointer),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+    use crate::logs::Logger;
+    use crate::rpc::{build_io_handler, RpcContext};
+    use std::sync::Arc;
+
+    fn test_io(logger: Arc<Logger>) -> RpcHandler {
+        build_io_handler(RpcContext {
+            note_store: None,
+            logger,
+            config: ServerConfig::default(),
+            subscriptions: Arc::default(),
+            metrics: Arc::default(),
+            drain: Arc::default(),
+        })
+    }
+
+    fn logged(body: &str, response: &str) -> LogEntry {
+        LogEntry::new("req-1".to_string(), "POST".to_string(), "/".to_string(), Vec::new(), None)
+            .with_body(body)
+            .with_response(response)
+    }
+
+    #[test]
+    fn test_replay_is_compared_and_linked() {
+        let logger = Arc::new(Logger::new());
+        let io = test_io(logger.clone());
+
+        let original = logged(
+            r#"{"jsonrpc":"2.0","method":"echo","params":["hi"],"id":1}"#,
+            r#"{"jsonrpc":"2.0","result":"hello","id":1}"#,
+        );
+        let result = replay(&io, &original, false).unwrap();
+        assert_eq!(result.matches, Some(false));
+        assert_eq!(result.differences, vec!["/result".to_string()]);
+
+        let entry = logger.find(&result.request_id).unwrap().unwrap();
+        assert_eq!(entry.method, "REPLAY");
+        assert_eq!(entry.replay_of.as_deref(), Some("req-1"));
+    }
+
+    #[test]
+    fn test_mutating_replay_requires_confirm() {
+        let io = test_io(Arc::new(Logger::new()));
+        let original = logged(
+            r#"[{"jsonrpc":"2.0","method":"echo","params":["a"],"id":1},{"jsonrpc":"2.0","method":"notes_delete","params":["n1"],"id":2}]"#,
+            "[]",
+        );
+
+        let error = replay(&io, &original, false).unwrap_err();
+        assert_eq!(error.data()["field"], "confirm");
+        assert!(replay(&io, &original, true).is_ok());
+    }
+}
@@ -0,0 +1,881 @@
+use jsonrpc_core::futures_util::future::Either;
+use jsonrpc_core::middleware::{NoopCallFuture, NoopFuture};
+use jsonrpc_core::{Call, Error, ErrorCode, Failure, Id, Metadata, MetaIoHandler, Middleware, Output, Params, Request, Response, Value, Version};
+use jsonrpc_pubsub::{typed, PubSubHandler, PubSubMetadata, Session, SubscriptionId};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use uuid::Uuid;
+
+use crate::auth::AuthContext;
+use crate::config::ServerConfig;
+use crate::drain::Drain;
+use crate::error::{
+    AppError, ERROR_CODE_CONFLICT, ERROR_CODE_FORBIDDEN, ERROR_CODE_NOT_FOUND, ERROR_CODE_STORAGE,
+    ERROR_CODE_UNAVAILABLE, ERROR_CODE_VALIDATION,
+};
+use crate::notes::{Note, NoteChange, NoteStore};
+use crate::openrpc::{self, param_schemas, result_schema, ParamsSchema, ResultSchema, DISCOVER_METHOD};
+use crate::params::{decode, NoParams, ParamField, ParamType, RpcParams};
+use crate::logs::{new_request_id, BatchLog, LogEntry, LogOrder, LogPage, LogQuery, Logger};
+use crate::metrics::{Metrics, Outcome};
+
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
+pub struct SystemInfo {
+    app_name: String,
+    version: String,
+    os: String,
+    arch: String,
+}
+
+// メモ変更の購読で使うJSON-RPCの通知名
+pub const NOTIFICATION_NOTES_CHANGED: &str = "notes_changed";
+
+// 購読メソッド（PubSubHandlerで登録し、WebSocket・IPCでのみ利用可能）
+pub const SUBSCRIBE_METHOD: &str = "notes_subscribe";
+pub const UNSUBSCRIBE_METHOD: &str = "notes_unsubscribe";
+const SUBSCRIPTION_SCOPE: &str = "notes:read";
+
+// RPC用パラメータ構造体の定義
+// FIELDS の順序が配列形式（位置引数）での順序になる
+#[derive(Deserialize, JsonSchema)]
+struct EchoParams {
+    message: String,
+}
+
+impl RpcParams for EchoParams {
+    const FIELDS: &'static [ParamField] = &[ParamField::required("message", ParamType::String)];
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct NoteIdParams {
+    id: String,
+}
+
+impl RpcParams for NoteIdParams {
+    const FIELDS: &'static [ParamField] = &[ParamField::required("id", ParamType::String)];
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct CreateNoteParams {
+    title: String,
+    content: String,
+}
+
+impl RpcParams for CreateNoteParams {
+    const FIELDS: &'static [ParamField] = &[
+        ParamField::required("title", ParamType::String),
+        ParamField::required("content", ParamType::String),
+    ];
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct UpdateNoteParams {
+    id: String,
+    title: Option<String>,
+    content: Option<String>,
+    // 指定した場合は版番号が一致するときだけ更新する
+    expected_revision: Option<u64>,
+}
+
+impl RpcParams for UpdateNoteParams {
+    const FIELDS: &'static [ParamField] = &[
+        ParamField::required("id", ParamType::String),
+        ParamField::optional("title", ParamType::String),
+        ParamField::optional("content", ParamType::String),
+        ParamField::optional("expected_revision", ParamType::Integer),
+    ];
+}
+
+impl RpcParams for LogQuery {
+    const FIELDS: &'static [ParamField] = &[
+        ParamField::optional("from", ParamType::Integer),
+        ParamField::optional("to", ParamType::Integer),
+        ParamField::optional("method", ParamType::String),
+        ParamField::optional("rpc_method", ParamType::String),
+        ParamField::optional("status", ParamType::IntegerUpTo(u16::MAX as u64)),
+        ParamField::optional("error_code", ParamType::SignedInteger),
+        ParamField::optional("text", ParamType::String),
+        ParamField::optional("token_id", ParamType::String),
+        ParamField::optional("origin", ParamType::String),
+        ParamField::optional("order", ParamType::OneOf(LogOrder::NAMES)),
+        ParamField::optional("offset", ParamType::Integer),
+        ParamField::optional("limit", ParamType::Integer),
+    ];
+}
+
+// RPCメソッドの実装の型
+type MethodHandler = fn(&RpcContext, Params) -> Result<Value, Error>;
+
+// RPCメソッドの定義（名前、呼び出しに必要なスコープグループ、実装）
+// params・result・errors はOpenRPCドキュメントの生成に使う
+pub struct RpcMethod {
+    pub name: &'static str,
+    pub scope: &'static str,
+    pub summary: &'static str,
+    handler: MethodHandler,
+    pub params: ParamsSchema,
+    pub result: ResultSchema,
+    // 返しうるアプリケーションエラーのコード
+    pub errors: &'static [i64],
+    // データを変更する（ログからの再実行に確認が必要）
+    pub mutating: bool,
+}
+
+// 公開するRPCメソッドの一覧
+// メソッドを追加する場合はここに1件追加する（スコープとOpenRPCドキュメントもここで決まる）
+pub const METHODS: &[RpcMethod] = &[
+    RpcMethod {
+        name: "echo",
+        scope: "system",
+        summary: "Returns the given message",
+        handler: echo,
+        params: param_schemas::<EchoParams>,
+        result: result_schema::<String>,
+        errors: &[],
+        mutating: false,
+    },
+    RpcMethod {
+        name: "system_info",
+        scope: "system",
+        summary: "Returns the application name, version, OS and architecture",
+        handler: system_info,
+        params: param_schemas::<NoParams>,
+        result: result_schema::<SystemInfo>,
+        errors: &[],
+        mutating: false,
+    },
+    RpcMethod {
+        name: "notes_list",
+        scope: "notes:read",
+        summary: "Lists all notes",
+        handler: notes_list,
+        params: param_schemas::<NoParams>,
+        result: result_schema::<Vec<Note>>,
+        errors: &[ERROR_CODE_UNAVAILABLE],
+        mutating: false,
+    },
+    RpcMethod {
+        name: "notes_get",
+        scope: "notes:read",
+        summary: "Returns the note with the given id, or null",
+        handler: notes_get,
+        params: param_schemas::<NoteIdParams>,
+        result: result_schema::<Option<Note>>,
+        errors: &[ERROR_CODE_UNAVAILABLE],
+        mutating: false,
+    },
+    RpcMethod {
+        name: "notes_create",
+        scope: "notes:write",
+        summary: "Creates a note",
+        handler: notes_create,
+        params: param_schemas::<CreateNoteParams>,
+        result: result_schema::<Note>,
+        errors: &[ERROR_CODE_VALIDATION, ERROR_CODE_STORAGE, ERROR_CODE_UNAVAILABLE],
+        mutating: true,
+    },
+    RpcMethod {
+        name: "notes_update",
+        scope: "notes:write",
+        summary: "Updates a note; fails with a conflict if expected_revision does not match",
+        handler: notes_update,
+        params: param_schemas::<UpdateNoteParams>,
+        result: result_schema::<Note>,
+        errors: &[
+            ERROR_CODE_NOT_FOUND,
+            ERROR_CODE_VALIDATION,
+            ERROR_CODE_CONFLICT,
+            ERROR_CODE_STORAGE,
+            ERROR_CODE_UNAVAILABLE,
+        ],
+        mutating: true,
+    },
+    RpcMethod {
+        name: "notes_delete",
+        scope: "notes:write",
+        summary: "Deletes a note and returns whether it existed",
+        handler: notes_delete,
+        params: param_schemas::<NoteIdParams>,
+        result: result_schema::<bool>,
+        errors: &[ERROR_CODE_STORAGE, ERROR_CODE_UNAVAILABLE],
+        mutating: true,
+    },
+    RpcMethod {
+        name: "server_logs_query",
+        scope: "logs:read",
+        summary: "Searches the request log (newest first unless order is oldest_first)",
+        handler: server_logs_query,
+        params: param_schemas::<LogQuery>,
+        result: result_schema::<LogPage>,
+        errors: &[ERROR_CODE_STORAGE],
+        mutating: false,
+    },
+];
+
+// データを変更するメソッドか（テーブルにないメソッドは変更しないものとする）
+pub fn is_mutating(name: &str) -> bool {
+    METHODS.iter().any(|method| method.name == name && method.mutating)
+}
+
+// すべてのメソッド名とスコープグループの組（購読メソッドを含む）
+pub fn method_scopes() -> impl Iterator<Item = (&'static str, &'static str)> {
+    METHODS
+        .iter()
+        .map(|method| (method.name, method.scope))
+        .chain([
+            (SUBSCRIBE_METHOD, SUBSCRIPTION_SCOPE),
+            (UNSUBSCRIBE_METHOD, SUBSCRIPTION_SCOPE),
+        ])
+}
+
+// RPCメソッドが参照するコンテキスト（すべてのトランスポートで共有）
+#[derive(Clone)]
+pub struct RpcContext {
+    pub note_store: Option<Arc<NoteStore>>,
+    pub logger: Arc<Logger>,
+    pub config: ServerConfig,
+    pub subscriptions: Arc<NoteSubscriptions>,
+    pub metrics: Arc<Metrics>,
+    // 停止処理中はWebSocket・IPCの新しいメッセージを受け付けない
+    pub drain: Arc<Drain>,
+}
+
+impl RpcContext {
+    fn note_store(&self) -> Result<&NoteStore, AppError> {
+        self.note_store
+            .as_deref()
+            .ok_or_else(|| AppError::Unavailable("Note store is not initialized".to_string()))
+    }
+}
+
+// リクエストを受け付けたトランスポート
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Transport {
+    #[default]
+    Http,
+    WebSocket,
+    Ipc,
+    // ログからの再実行（replay_log_entry コマンド）
+    Replay,
+}
+
+impl Transport {
+    // ログエントリの method 欄に表示する名前
+    fn label(&self) -> &'static str {
+        match self {
+            Transport::Http => "HTTP",
+            Transport::WebSocket => "WS",
+            Transport::Ipc => "IPC",
+            Transport::Replay => "REPLAY",
+        }
+    }
+}
+
+// RPCメソッドに渡されるリクエストごとのメタデータ
+#[derive(Clone, Debug, Default)]
+pub struct RpcMeta {
+    pub auth: AuthContext,
+    // WebSocket・IPC接続のセッション（購読に必要。HTTPではNone）
+    pub session: Option<Arc<Session>>,
+    pub transport: Transport,
+    // 接続先（WebSocketのリソースパス、IPCのソケットパス）
+    pub endpoint: Option<String>,
+    // リクエストID（HTTPはリクエストごと、WebSocket・IPCはメッセージごとに割り当てる）
+    pub request_id: Option<String>,
+    // バッチリクエストの各呼び出しの記録先（単一の呼び出しではNone）
+    pub batch: Option<Arc<BatchLog>>,
+    // 再実行の場合は元のリクエストID
+    pub replay_of: Option<String>,
+}
+
+impl Metadata for RpcMeta {}
+
+impl PubSubMetadata for RpcMeta {
+    fn session(&self) -> Option<Arc<Session>> {
+        self.session.clone()
+    }
+}
+
+// RPCハンドラーの型
+// スコープ外の呼び出しもバッチの記録に含めるため、TransportLogMiddleware を先に通す
+pub type RpcMiddleware = (TransportLogMiddleware, ScopeMiddleware);
+pub type RpcHandler = MetaIoHandler<RpcMeta, RpcMiddleware>;
+
+// HTTP・WebSocket・IPCで共有するRPCハンドラーを構築
+pub fn build_io_handler(ctx: RpcContext) -> RpcHandler {
+    let mut io = RpcHandler::with_middleware((
+        TransportLogMiddleware {
+            logger: ctx.logger.clone(),
+            metrics: ctx.metrics.clone(),
+            drain: ctx.drain.clone(),
+            max_batch_size: ctx.config.max_batch_size,
+        },
+        ScopeMiddleware,
+    ));
+
+    for method in METHODS {
+        let ctx = ctx.clone();
+        let handler = method.handler;
+        io.add_method_with_meta(method.name, move |params: Params, meta: RpcMeta| {
+            let ctx = ctx.clone();
+            async move { handler(&ctx, params).map_err(|error| with_request_id(error, &meta)) }
+        });
+    }
+
+    // rpc.discover: OpenRPCドキュメントを返す（どのトークンからも呼び出せる）
+    let document = openrpc::document(&ctx.config);
+    io.add_method(DISCOVER_METHOD, move |_params: Params| {
+        let document = document.clone();
+        async move { Ok(document) }
+    });
+
+    if !subscriptions_enabled(&ctx.config) {
+        return io;
+    }
+
+    // notes_subscribe / notes_unsubscribe: メモの変更を購読
+    let mut io = PubSubHandler::new(io);
+    let subscriptions = ctx.subscriptions.clone();
+    let unsubscriptions = ctx.subscriptions.clone();
+    io.add_subscription(
+        NOTIFICATION_NOTES_CHANGED,
+        (SUBSCRIBE_METHOD, move |_params: Params, _meta: RpcMeta, subscriber| {
+            subscriptions.subscribe(typed::Subscriber::new(subscriber));
+        }),
+        (UNSUBSCRIBE_METHOD, move |id: SubscriptionId, _meta: Option<RpcMeta>| {
+            let removed = unsubscriptions.unsubscribe(&id);
+            async move { Ok(Value::Bool(removed)) }
+        }),
+    );
+    io.into()
+}
+
+// エラーの data にリクエストIDを追加（ログとの突き合わせに使う）
+fn with_request_id(mut error: Error, meta: &RpcMeta) -> Error {
+    if let Some(request_id) = &meta.request_id {
+        match error.data.as_mut() {
+            Some(Value::Object(data)) => {
+                data.insert("request_id".to_string(), Value::String(request_id.clone()));
+            }
+            None => error.data = Some(serde_json::json!({ "request_id": request_id })),
+            Some(_) => {}
+        }
+    }
+    error
+}
+
+// 購読はセッションを持つトランスポート（WebSocket・IPC）が有効な場合のみ登録
+pub fn subscriptions_enabled(config: &ServerConfig) -> bool {
+    config.ws_enabled || config.ipc_enabled
+}
+
+// Echo method
+fn echo(_ctx: &RpcContext, params: Params) -> Result<Value, Error> {
+    let params: EchoParams = decode(params)?;
+
+    Ok(Value::String(params.message))
+}
+
+// System info method - パラメータなし
+fn system_info(_ctx: &RpcContext, params: Params) -> Result<Value, Error> {
+    decode::<NoParams>(params)?;
+
+    let system_info = SystemInfo {
+        app_name: env!("CARGO_PKG_NAME").to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        os: env::consts::OS.to_string(),
+        arch: env::consts::ARCH.to_string(),
+    };
+
+    Ok(serde_json::to_value(system_info).unwrap())
+}
+
+// notes_list: すべてのメモをリスト表示 - パラメータなし
+fn notes_list(ctx: &RpcContext, params: Params) -> Result<Value, Error> {
+    decode::<NoParams>(params)?;
+
+    // すべてのメモを取得
+    let notes = ctx.note_store()?.list();
+
+    // メモリストをJSONに変換して返す
+    to_value(notes)
+}
+
+// notes_get: 指定されたIDのメモを取得
+fn notes_get(ctx: &RpcContext, params: Params) -> Result<Value, Error> {
+    let params: NoteIdParams = decode(params)?;
+
+    // メモを取得
+    match ctx.note_store()?.get(&params.id) {
+        Some(note) => to_value(note),
+        None => Ok(Value::Null),
+    }
+}
+
+// notes_create: 新しいメモを作成
+fn notes_create(ctx: &RpcContext, params: Params) -> Result<Value, Error> {
+    let params: CreateNoteParams = decode(params)?;
+
+    // メモを作成
+    let note = ctx.note_store()?.create(params.title, params.content)?;
+    to_value(note)
+}
+
+// notes_update: メモを更新（title・content・expected_revision は省略可能）
+fn notes_update(ctx: &RpcContext, params: Params) -> Result<Value, Error> {
+    let params: UpdateNoteParams = decode(params)?;
+
+    // メモを更新
+    let note = ctx
+        .note_store()?
+        .update(&params.id, params.title, params.content, params.expected_revision)?;
+    to_value(note)
+}
+
+// notes_delete: メモを削除
+fn notes_delete(ctx: &RpcContext, params: Params) -> Result<Value, Error> {
+    let params: NoteIdParams = decode(params)?;
+
+    // メモを削除
+    let removed = ctx.note_store()?.delete(&params.id)?;
+    Ok(Value::Bool(removed))
+}
+
+// server_logs_query: 条件に一致するアクセスログを検索
+fn server_logs_query(ctx: &RpcContext, params: Params) -> Result<Value, Error> {
+    let query: LogQuery = decode(params)?;
+
+    let page = ctx.logger.query(&query)?;
+    to_value(page)
+}
+
+// 戻り値をJSONに変換
+fn to_value<T: Serialize>(value: T) -> Result<Value, Error> {
+    serde_json::to_value(value).map_err(|e| AppError::internal(e).into())
+}
+
+// トークンのスコープを検証するJSON-RPCミドルウェア
+#[derive(Clone, Debug, Default)]
+pub struct ScopeMiddleware;
+
+impl Middleware<RpcMeta> for ScopeMiddleware {
+    type Future = NoopFuture;
+    type CallFuture = NoopCallFuture;
+
+    fn on_call<F, X>(&self, call: Call, meta: RpcMeta, next: F) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, RpcMeta) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        let (method, id, jsonrpc) = match &call {
+            Call::MethodCall(method_call) => (&method_call.method, Some(method_call.id.clone()), method_call.jsonrpc),
+            Call::Notification(notification) => (&notification.method, None, notification.jsonrpc),
+            Call::Invalid { .. } => return Either::Right(next(call, meta)),
+        };
+
+        if method == DISCOVER_METHOD || meta.auth.allows(method) {
+            return Either::Right(next(call, meta));
+        }
+
+        // 通知の場合はレスポンスを返さない
+        let output = id.map(|id| {
+            Output::Failure(Failure {
+                jsonrpc,
+                error: Error {
+                    code: ErrorCode::ServerError(ERROR_CODE_FORBIDDEN),
+                    message: format!("Method '{}' is not allowed for this token", method),
+                    data: Some(serde_json::json!({
+                        "kind": "forbidden",
+                        "method": method,
+                        "request_id": meta.request_id,
+                    })),
+                },
+                id,
+            })
+        });
+
+        Either::Left(Box::pin(async move { output }))
+    }
+}
+
+// WebSocket・IPCのリクエストと再実行をログに記録するJSON-RPCミドルウェア
+// HTTPのリクエストはリクエストミドルウェア（LoggingMiddleware）で記録する
+// バッチの呼び出し数の検証、バッチの各呼び出しの記録とメトリクスの集計はすべてのトランスポートで行う
+#[derive(Clone)]
+pub struct TransportLogMiddleware {
+    logger: Arc<Logger>,
+    metrics: Arc<Metrics>,
+    drain: Arc<Drain>,
+    max_batch_size: usize,
+}
+
+impl TransportLogMiddleware {
+    // 呼び出し数が上限を超えるバッチに対するエラーレスポンス
+    fn reject_oversized_batch(&self, request: &Request, meta: &RpcMeta) -> Option<Response> {
+        let Request::Batch(calls) = request else {
+            return None;
+        };
+        if calls.len() <= self.max_batch_size {
+            return None;
+        }
+
+        Some(Response::Single(Output::Failure(Failure {
+            jsonrpc: Some(Version::V2),
+            error: Error {
+                code: ErrorCode::InvalidRequest,
+                message: format!(
+                    "Batch of {} calls exceeds the maximum batch size of {}",
+                    calls.len(),
+                    self.max_batch_size
+                ),
+                data: Some(serde_json::json!({
+                    "kind": "batch_too_large",
+                    "batch_size": calls.len(),
+                    "max_batch_size": self.max_batch_size,
+                    "request_id": meta.request_id,
+                })),
+            },
+            id: Id::Null,
+        })))
+    }
+
+    // 停止処理中に受信したメッセージに対するエラーレスポンス
+    fn reject_shutting_down(&self, meta: &RpcMeta) -> Response {
+        let mut error: Error = AppError::Unavailable("Server is shutting down".to_string()).into();
+        if let Some(Value::Object(data)) = error.data.as_mut() {
+            data.insert("request_id".to_string(), meta.request_id.clone().into());
+        }
+        Response::Single(Output::Failure(Failure {
+            jsonrpc: Some(Version::V2),
+            error,
+            id: Id::Null,
+        }))
+    }
+}
+
+impl Middleware<RpcMeta> for TransportLogMiddleware {
+    type Future = NoopFuture;
+    type CallFuture = NoopCallFuture;
+
+    fn on_request<F, X>(&self, request: Request, mut meta: RpcMeta, next: F) -> Either<Self::Future, X>
+    where
+        F: Fn(Request, RpcMeta) -> X + Send + Sync,
+        X: Future<Output = Option<Response>> + Send + 'static,
+    {
+        let in_flight = self.metrics.start_request();
+        if meta.transport == Transport::Http {
+            // HTTPではバッチの記録先を LoggingMiddleware が用意する
+            if let Some(response) = self.reject_oversized_batch(&request, &meta) {
+                return Either::Left(Box::pin(async move { Some(response) }));
+            }
+            let response = next(request, meta);
+            return Either::Left(Box::pin(async move {
+                let _in_flight = in_flight;
+                response.await
+            }));
+        }
+
+        // WebSocket・IPCのメタデータは接続ごとのため、メッセージごとにリクエストIDを割り当てる
+        // 再実行では呼び出し元が割り当てたIDを使う
+        let request_id = meta.request_id.take().unwrap_or_else(new_request_id);
+        meta.request_id = Some(request_id.clone());
+        meta.batch = matches!(request, Request::Batch(_)).then(|| Arc::new(BatchLog::default()));
+
+        // メッセージの完了時に1件のエントリとして記録する
+        let started = Instant::now();
+        let entry = LogEntry::new(
+            request_id,
+            meta.transport.label().to_string(),
+            meta.endpoint.clone().unwrap_or_default(),
+            Vec::new(),
+            meta.auth.token_id.clone(),
+        )
+        .with_replay_of(meta.replay_of.clone());
+        let entry = match serde_json::to_string(&request) {
+            Ok(body) => entry.with_body(&body),
+            Err(_) => entry,
+        }
+        .with_call(&request);
+
+        // HTTPは LoggingMiddleware で受け付けた時点から処理中として数える
+        let drain_guard = self.drain.enter();
+        let rejection = match drain_guard {
+            Some(_) => self.reject_oversized_batch(&request, &meta),
+            None => Some(self.reject_shutting_down(&meta)),
+        };
+
+        let logger = self.logger.clone();
+        if let Some(response) = rejection {
+            let entry = match serde_json::to_string(&response) {
+                Ok(response_str) => entry.with_response(&response_str),
+                Err(_) => entry,
+            };
+            logger.record(entry.with_elapsed(started.elapsed()));
+            return Either::Left(Box::pin(async move { Some(response) }));
+        }
+
+        let batch = meta.batch.clone();
+        logger.started(&entry);
+        let response = next(request, meta);
+        Either::Left(Box::pin(async move {
+            let response = response.await;
+            drop(in_flight);
+            drop(drain_guard);
+            let entry = match response.as_ref().and_then(|r| serde_json::to_string(r).ok()) {
+                Some(response_str) => entry.with_response(&response_str),
+                None => entry,
+            };
+            let entry = match batch {
+                Some(batch) => entry.with_batch(&batch),
+                None => entry,
+            };
+            logger.record(entry.with_elapsed(started.elapsed()));
+            response
+        }))
+    }
+
+    // 呼び出しごとの件数と所要時間を集計し、バッチの場合はメソッド・パラメータ・結果も記録
+    fn on_call<F, X>(&self, call: Call, meta: RpcMeta, next: F) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, RpcMeta) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        let method = metrics_label(&call);
+        let batch = meta.batch.clone();
+        let index = batch.as_ref().map(|batch| batch.start(&call));
+        let metrics = self.metrics.clone();
+        let started = Instant::now();
+        let output = next(call, meta);
+        Either::Left(Box::pin(async move {
+            let output = output.await;
+            let outcome = match &output {
+                Some(Output::Failure(_)) => Outcome::Error,
+                _ => Outcome::Success,
+            };
+            metrics.observe_call(method, outcome, started.elapsed());
+            if let (Some(batch), Some(index)) = (batch, index) {
+                batch.finish(index, output.as_ref(), started.elapsed());
+            }
+            output
+        }))
+    }
+}
+
+// メトリクスのラベルに使うメソッド名（ラベルの種類が増え続けないよう、未定義のメソッドはまとめる）
+fn metrics_label(call: &Call) -> &'static str {
+    let method = match call {
+        Call::MethodCall(method_call) => method_call.method.as_str(),
+        Call::Notification(notification) => notification.method.as_str(),
+        Call::Invalid { .. } => return "invalid",
+    };
+    if method == DISCOVER_METHOD {
+        return DISCOVER_METHOD;
+    }
+    method_scopes()
+        .map(|(name, _)| name)
+        .find(|name| *name == method)
+        .unwrap_or("unknown")
+}
+
+// notes_subscribe で登録された購読者
+#[derive(Default)]
+pub struct NoteSubscriptions {
+    sinks: Mutex<HashMap<SubscriptionId, typed::Sink<NoteChange>>>,
+}
+
+impl NoteSubscriptions {
+    fn subscribe(&self, subscriber: typed::Subscriber<NoteChange>) {
+        let id = SubscriptionId::String(Uuid::new_v4().to_string());
+        if let Ok(sink) = subscriber.assign_id(id.clone()) {
+            self.sinks.lock().unwrap().insert(id, sink);
+        }
+    }
+
+    fn unsubscribe(&self, id: &SubscriptionId) -> bool {
+        self.sinks.lock().unwrap().remove(id).is_some()
+    }
+
+    // すべての購読者に通知（切断済みで送信できない購読者は取り除く）
+    pub fn notify(&self, change: &NoteChange) {
+        self.sinks
+            .lock()
+            .unwrap()
+            .retain(|_, sink| sink.notify(Ok(change.clone())).is_ok());
+    }
+
+    pub fn clear(&self) {
+        self.sinks.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // テスト用の一時ディレクトリにメモを保存するコンテキスト
+    fn test_context() -> RpcContext {
+        let data_dir = env::temp_dir().join(format!("rpc-test-{}", Uuid::new_v4()));
+        RpcContext {
+            note_store: Some(Arc::new(NoteStore::open(&data_dir).unwrap())),
+            logger: Arc::new(Logger::new()),
+            config: ServerConfig::default(),
+            subscriptions: Arc::new(NoteSubscriptions::default()),
+            metrics: Arc::new(Metrics::default()),
+            drain: Arc::new(Drain::default()),
+        }
+    }
+
+    fn call(io: &RpcHandler, meta: RpcMeta, request: &str) -> Value {
+        let response = io.handle_request_sync(request, meta).unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn test_in_process_notes_roundtrip() {
+        let io = build_io_handler(test_context());
+        let meta = RpcMeta {
+            auth: AuthContext::full_access(None),
+            ..RpcMeta::default()
+        };
+
+        let created = call(
+            &io,
+            meta.clone(),
+            r#"{"jsonrpc":"2.0","method":"notes_create","params":{"title":"t","content":"c"},"id":1}"#,
+        );
+        let id = created["result"]["id"].as_str().unwrap().to_string();
+
+        let fetched = call(
+            &io,
+            meta,
+            &format!(r#"{{"jsonrpc":"2.0","method":"notes_get","params":["{}"],"id":2}}"#, id),
+        );
+        assert_eq!(fetched["result"]["title"], "t");
+    }
+
+    #[test]
+    fn test_note_errors_have_stable_codes() {
+        let io = build_io_handler(test_context());
+        let meta = RpcMeta {
+            auth: AuthContext::full_access(None),
+            request_id: Some("req-1".to_string()),
+            ..RpcMeta::default()
+        };
+
+        let missing = call(
+            &io,
+            meta.clone(),
+            r#"{"jsonrpc":"2.0","method":"notes_update","params":{"id":"missing","title":"t"},"id":1}"#,
+        );
+        assert_eq!(missing["error"]["code"], crate::error::ERROR_CODE_NOT_FOUND);
+        assert_eq!(missing["error"]["data"]["kind"], "not_found");
+        assert_eq!(missing["error"]["data"]["request_id"], "req-1");
+
+        let created = call(
+            &io,
+            meta.clone(),
+            r#"{"jsonrpc":"2.0","method":"notes_create","params":["t","c"],"id":2}"#,
+        );
+        let id = created["result"]["id"].as_str().unwrap().to_string();
+
+        let conflict = call(
+            &io,
+            meta,
+            &format!(
+                r#"{{"jsonrpc":"2.0","method":"notes_update","params":{{"id":"{}","content":"x","expected_revision":5}},"id":3}}"#,
+                id
+            ),
+        );
+        assert_eq!(conflict["error"]["code"], crate::error::ERROR_CODE_CONFLICT);
+        assert_eq!(conflict["error"]["data"]["actual_revision"], 1);
+    }
+
+    #[test]
+    fn test_oversized_batch_is_rejected() {
+        let ctx = RpcContext {
+            config: ServerConfig {
+                max_batch_size: 2,
+                ..ServerConfig::default()
+            },
+            ..test_context()
+        };
+        let io = build_io_handler(ctx);
+        let meta = RpcMeta {
+            auth: AuthContext::full_access(None),
+            ..RpcMeta::default()
+        };
+
+        let call_echo = r#"{"jsonrpc":"2.0","method":"echo","params":["a"],"id":1}"#;
+        let accepted = call(&io, meta.clone(), &format!("[{},{}]", call_echo, call_echo));
+        assert_eq!(accepted.as_array().map(Vec::len), Some(2));
+
+        let rejected = call(&io, meta, &format!("[{},{},{}]", call_echo, call_echo, call_echo));
+        assert_eq!(rejected["error"]["code"], -32600);
+        assert_eq!(rejected["error"]["data"]["kind"], "batch_too_large");
+        assert_eq!(rejected["id"], Value::Null);
+    }
+
+    #[test]
+    fn test_scope_is_enforced() {
+        let io = build_io_handler(test_context());
+        let meta = RpcMeta {
+            auth: AuthContext {
+                token_id: Some("reader".to_string()),
+                scopes: vec!["notes:read".to_string()],
+            },
+            ..RpcMeta::default()
+        };
+
+        let response = call(
+            &io,
+            meta,
+            r#"{"jsonrpc":"2.0","method":"notes_delete","params":["x"],"id":1}"#,
+        );
+        assert_eq!(response["error"]["code"], ERROR_CODE_FORBIDDEN);
+    }
+
+    #[test]
+    fn test_log_query_errors_name_the_field() {
+        let io = build_io_handler(test_context());
+        let meta = RpcMeta {
+            auth: AuthContext::full_access(None),
+            ..RpcMeta::default()
+        };
+
+        let accepted = call(
+            &io,
+            meta.clone(),
+            r#"{"jsonrpc":"2.0","method":"server_logs_query","params":{"status":200,"order":"oldest_first"},"id":1}"#,
+        );
+        assert!(accepted["result"].is_object());
+
+        let status = call(
+            &io,
+            meta.clone(),
+            r#"{"jsonrpc":"2.0","method":"server_logs_query","params":{"status":70000},"id":2}"#,
+        );
+        assert_eq!(status["error"]["code"], -32602);
+        assert_eq!(status["error"]["data"]["field"], "status");
+        assert_eq!(
+            status["error"]["message"],
+            "Invalid parameter 'status': expected integer between 0 and 65535, got 70000"
+        );
+
+        let order = call(
+            &io,
+            meta,
+            r#"{"jsonrpc":"2.0","method":"server_logs_query","params":{"order":"sideways"},"id":3}"#,
+        );
+        assert_eq!(order["error"]["code"], -32602);
+        assert_eq!(order["error"]["data"]["field"], "order");
+        assert_eq!(
+            order["error"]["message"],
+            "Invalid parameter 'order': expected one of newest_first, oldest_first, got \"sideways\""
+        );
+    }
+}
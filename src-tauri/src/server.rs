@@ -1,147 +1,735 @@
-use jsonrpc_core::{IoHandler, Params, Value, Error};
-use jsonrpc_http_server::{Server, ServerBuilder, RequestMiddleware, RequestMiddlewareAction, hyper};
-use serde::{Deserialize, Serialize};
-use std::env;
-use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
-use std::collections::VecDeque;
-use tauri::{AppHandle, Manager};
-
-// Notes型を使用しているが直接インポートせずにcrate::notes通してアクセス
-// use crate::notes::{Note, NoteStore};
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct SystemInfo {
-    app_name: String,
-    version: String,
-    os: String,
-    arch: String,
-}
+use jsonrpc_http_server::hyper::body::HttpBody;
+use jsonrpc_http_server::cors::AccessControlAllowHeaders;
+use jsonrpc_http_server::hyper::service::Service;
+use jsonrpc_http_server::{cors_allow_origin, tokio, AccessControlAllowOrigin, AllowCors, RequestMiddleware, RequestMiddlewareAction, RestApi, Rpc, ServerHandler, hyper};
+use jsonrpc_ipc_server as ipc_server;
+use jsonrpc_pubsub::Session;
+use jsonrpc_ws_server as ws_server;
+use serde::Serialize;
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct LogEntry {
-    timestamp: u64,
-    method: String,
-    uri: String,
-    headers: String,
-    body: Option<String>,
-    response: Option<String>,
-}
+use crate::auth::{AuthContext, AuthError, TokenAuth};
+use crate::config::ServerConfig;
+use crate::drain::Drain;
+use crate::error::AppError;
+use crate::limits::{client_key, ConcurrencyLimit, RateLimiter};
+use crate::metrics::{Gauges, Metrics};
+use crate::notes::NoteStore;
+use crate::logs::{new_request_id, sanitize_request_id, BatchLog, LogEntry, LogEvent, LogHeader, LogListener, Logger};
+use crate::openrpc::{self, OPENRPC_PATH};
+use crate::rpc::{build_io_handler, NoteSubscriptions, RpcContext, RpcHandler, RpcMeta, RpcMiddleware, Transport};
+use crate::settings::LaunchSettings;
+use crate::tls::TlsIdentity;
+use tokio_rustls::TlsAcceptor;
 
-// RPC用パラメータ構造体の定義
-#[derive(Deserialize)]
-struct EchoParams {
-    message: String,
+// server-started イベントのペイロード（外部スクリプト向けのエンドポイントファイルにも書き出す）
+#[derive(Serialize, Debug, Clone)]
+pub struct ServerStartedPayload {
+    // HTTPリスナーのURLとポート（無効な場合はNone）
+    url: Option<String>,
+    port: Option<u16>,
+    // WebSocketリスナーのURL（無効な場合はNone）
+    ws_url: Option<String>,
+    // IPCソケットのパス（無効な場合はNone）
+    ipc_path: Option<String>,
+    // HTTPSの場合の証明書のSHA-256フィンガープリント（HTTPの場合はNone）
+    tls_fingerprint: Option<String>,
 }
 
-#[derive(Deserialize)]
-struct NoteIdParams {
-    id: String,
+// server-stopping イベントのペイロード
+#[derive(Serialize, Debug, Clone)]
+pub struct ServerStoppingPayload {
+    // 処理中のリクエストの完了を待つ最大秒数
+    timeout_secs: u64,
+    in_flight: usize,
 }
 
-#[derive(Deserialize)]
-struct CreateNoteParams {
-    title: String,
-    content: String,
+// server-stopped イベントのペイロード
+#[derive(Serialize, Debug, Clone)]
+pub struct ServerStoppedPayload {
+    // 処理中のリクエストが時間内に終わらず、強制的に閉じたか
+    forced: bool,
+    // 強制的に閉じた時点で処理中だったリクエストの数
+    unfinished_requests: usize,
 }
 
-#[derive(Deserialize)]
-struct UpdateNoteParams {
-    id: String,
-    title: Option<String>,
-    content: Option<String>,
+// イベント名の定数
+const EVENT_SERVER_STARTED: &str = "server-started";
+const EVENT_SERVER_STOPPING: &str = "server-stopping";
+const EVENT_SERVER_STOPPED: &str = "server-stopped";
+const EVENT_LOG_APPENDED: &str = "server-log-appended";
+const EVENT_LOGS_CLEARED: &str = "server-logs-cleared";
+
+// 外部スクリプトがサーバーを見つけるためのファイル（アプリのデータディレクトリに保存）
+const ENDPOINT_FILE_NAME: &str = "server-endpoint.json";
+
+// アクセスログを保存するディレクトリ（アプリのデータディレクトリ内）
+const LOG_DIR_NAME: &str = "logs";
+
+// 同時処理数の上限に達した場合に返す Retry-After（秒）
+const IN_FLIGHT_RETRY_AFTER_SECS: u64 = 1;
+
+// 接続の受け付けに失敗した場合（ファイルディスクリプタの枯渇など）に待つ時間
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+// リクエストIDを受け取り・返すヘッダー（サーバーが生成したIDを返し、受け取ったIDは別のヘッダーで返す）
+const REQUEST_ID_HEADER: &str = "x-request-id";
+const CLIENT_REQUEST_ID_HEADER: &str = "x-client-request-id";
+
+// ブラウザのクライアントに読み取りを許可するヘッダー
+const EXPOSED_REQUEST_ID_HEADERS: &str = "x-request-id, x-client-request-id";
+
+// 外部の監視向けのエンドポイント（JSON-RPCを通さずミドルウェアで応答し、ログには記録しない）
+const HEALTH_PATH: &str = "/health";
+const READY_PATH: &str = "/ready";
+const METRICS_PATH: &str = "/metrics";
+
+// HTTPトランスポートの実装（Bearerトークン認証とログ記録も行う）
+// JSON-RPCのPOSTリクエストはこのミドルウェアで処理し、受信した本文と送信したレスポンスをそのまま記録する
+struct LoggingMiddleware {
+    logger: Arc<Logger>,
+    auth: Arc<TokenAuth>,
+    io: RpcHandler,
+    // CORSの許可オリジン
+    cors_domains: Option<Vec<AccessControlAllowOrigin>>,
+    // GET /openrpc.json で返すOpenRPCドキュメント
+    openrpc_document: Arc<String>,
+    metrics: Arc<Metrics>,
+    // GET /ready と GET /metrics で参照する（NoteStoreが設定されていない場合はNone）
+    note_store: Option<Arc<NoteStore>>,
+    // リクエスト本文の上限
+    max_body_bytes: usize,
+    // 設定で無効にした制限はNone
+    rate_limiter: Option<Arc<RateLimiter>>,
+    concurrency_limit: Option<Arc<ConcurrencyLimit>>,
+    // 停止処理中は新しいリクエストを受け付けない
+    drain: Arc<Drain>,
+    // クライアントが接続したスキーム（HTTPSの場合はTLSを終端した後の通信を受け取る）
+    scheme: &'static str,
 }
 
-// ログ記録用の構造体
-struct Logger {
-    logs: Mutex<VecDeque<LogEntry>>,
+// ミドルウェアが返すレスポンス（ログに記録するため本文を文字列で保持する）
+struct HttpReply {
+    status: hyper::StatusCode,
+    content_type: &'static str,
+    body: String,
+    // Retry-After ヘッダー（秒）
+    retry_after: Option<u64>,
 }
 
-impl Logger {
-    fn new() -> Self {
+impl HttpReply {
+    fn json(body: String) -> Self {
         Self {
-            logs: Mutex::new(VecDeque::new()),
+            status: hyper::StatusCode::OK,
+            content_type: "application/json; charset=utf-8",
+            body,
+            retry_after: None,
         }
     }
 
-    fn log_request(&self, method: String, uri: String, headers: String, body: Option<String>) {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
-        
-        let log_entry = LogEntry {
-            timestamp,
-            method,
-            uri,
-            headers,
+    // Prometheusのテキスト形式
+    fn metrics(body: String) -> Self {
+        Self {
+            status: hyper::StatusCode::OK,
+            content_type: "text/plain; version=0.0.4; charset=utf-8",
             body,
-            response: None,
-        };
-        
-        let mut logs = self.logs.lock().unwrap();
-        logs.push_back(log_entry);
-        
-        while logs.len() > 50 {
-            logs.pop_front();
+            retry_after: None,
         }
     }
 
-    fn log_response(&self, response_str: String) {
-        let mut logs = self.logs.lock().unwrap();
-        if let Some(last_entry) = logs.back_mut() {
-            last_entry.response = Some(response_str);
+    fn text(status: hyper::StatusCode, body: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: format!("{}\n", body),
+            retry_after: None,
         }
     }
 
-    fn get_logs(&self) -> Vec<LogEntry> {
-        let logs = self.logs.lock().unwrap();
-        logs.iter().cloned().collect()
+    // レスポンスをログに記録してからhyperのレスポンスに変換
+    fn finish(self, logger: &Logger, ids: &RequestIds, entry: LogEntry, started: Instant, cors_origin: Option<hyper::header::HeaderValue>) -> hyper::Response<hyper::Body> {
+        logger.record(
+            entry
+                .with_status(self.status.as_u16())
+                .with_response(&self.body)
+                .with_elapsed(started.elapsed()),
+        );
+        self.into_response(ids, cors_origin)
     }
 
-    fn clear_logs(&self) {
-        let mut logs = self.logs.lock().unwrap();
-        logs.clear();
+    // ログに記録せずにhyperのレスポンスに変換
+    fn into_response(self, ids: &RequestIds, cors_origin: Option<hyper::header::HeaderValue>) -> hyper::Response<hyper::Body> {
+        let mut response = hyper::Response::new(hyper::Body::from(self.body));
+        *response.status_mut() = self.status;
+        let headers = response.headers_mut();
+        headers.insert(
+            hyper::header::CONTENT_TYPE,
+            hyper::header::HeaderValue::from_static(self.content_type),
+        );
+        set_request_id_headers(headers, ids);
+        if let Some(retry_after) = self.retry_after {
+            headers.insert(hyper::header::RETRY_AFTER, hyper::header::HeaderValue::from(retry_after));
+        }
+        if let Some(origin) = cors_origin {
+            headers.insert(hyper::header::VARY, hyper::header::HeaderValue::from_static("origin"));
+            headers.insert(
+                hyper::header::ACCESS_CONTROL_ALLOW_METHODS,
+                hyper::header::HeaderValue::from_static("OPTIONS, POST"),
+            );
+            headers.insert(hyper::header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+            headers.insert(
+                hyper::header::ACCESS_CONTROL_EXPOSE_HEADERS,
+                hyper::header::HeaderValue::from_static(EXPOSED_REQUEST_ID_HEADERS),
+            );
+        }
+        response
     }
 }
 
-// ロギングミドルウェアの実装
-struct LoggingMiddleware {
-    logger: Arc<Logger>,
+// リクエストID（X-Request-Id）と、クライアントが指定したID（X-Client-Request-Id）
+struct RequestIds {
+    id: String,
+    client_id: Option<String>,
+}
+
+fn set_request_id_headers(headers: &mut hyper::HeaderMap, ids: &RequestIds) {
+    if let Ok(value) = hyper::header::HeaderValue::from_str(&ids.id) {
+        headers.insert(REQUEST_ID_HEADER, value);
+    }
+    if let Some(Ok(value)) = ids.client_id.as_deref().map(hyper::header::HeaderValue::from_str) {
+        headers.insert(CLIENT_REQUEST_ID_HEADER, value);
+    }
+}
+
+// GET /health の応答（プロセスが応答できれば常に200）
+fn health_reply() -> HttpReply {
+    HttpReply::json(serde_json::json!({ "status": "ok", "version": env!("CARGO_PKG_VERSION") }).to_string())
+}
+
+// GET /ready の応答（停止処理中でなく、NoteStoreが設定されていて保存先に書き込める場合のみ200）
+fn ready_reply(note_store: Option<&NoteStore>, draining: bool) -> HttpReply {
+    if draining {
+        return HttpReply {
+            status: hyper::StatusCode::SERVICE_UNAVAILABLE,
+            ..HttpReply::json(serde_json::json!({ "status": "stopping", "checks": {} }).to_string())
+        };
+    }
+
+    let check = match note_store {
+        Some(store) => store.check_writable().map_err(|e| e.to_string()),
+        None => Err("Note store is not initialized".to_string()),
+    };
+    let (status, body) = match check {
+        Ok(()) => (
+            hyper::StatusCode::OK,
+            serde_json::json!({ "status": "ready", "checks": { "notes": "ok" } }),
+        ),
+        Err(reason) => (
+            hyper::StatusCode::SERVICE_UNAVAILABLE,
+            serde_json::json!({ "status": "not_ready", "checks": { "notes": reason } }),
+        ),
+    };
+    HttpReply {
+        status,
+        ..HttpReply::json(body.to_string())
+    }
+}
+
+// リクエスト数の制限を超えた場合の429レスポンス（本文はJSON-RPCのエラー）
+fn rate_limited_reply(limit: &str, retry_after: Duration, request_id: &str) -> HttpReply {
+    let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    let mut error: jsonrpc_core::Error = AppError::RateLimited {
+        limit: limit.to_string(),
+        retry_after_secs,
+    }
+    .into();
+    if let Some(serde_json::Value::Object(data)) = error.data.as_mut() {
+        data.insert("request_id".to_string(), request_id.into());
+    }
+
+    let failure = jsonrpc_core::Failure {
+        jsonrpc: Some(jsonrpc_core::Version::V2),
+        error,
+        id: jsonrpc_core::Id::Null,
+    };
+    HttpReply {
+        status: hyper::StatusCode::TOO_MANY_REQUESTS,
+        retry_after: Some(retry_after_secs),
+        ..HttpReply::json(format!("{}\n", serde_json::to_string(&failure).unwrap_or_default()))
+    }
+}
+
+// 認証失敗時の401レスポンス
+fn unauthorized_response(error: AuthError, ids: &RequestIds) -> hyper::Response<hyper::Body> {
+    let mut response = hyper::Response::new(hyper::Body::from(format!("{}\n", error)));
+    *response.status_mut() = hyper::StatusCode::UNAUTHORIZED;
+    response.headers_mut().insert(
+        hyper::header::WWW_AUTHENTICATE,
+        hyper::header::HeaderValue::from_static("Bearer"),
+    );
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    set_request_id_headers(response.headers_mut(), ids);
+    response
+}
+
+// JSONのContent-Typeか（text/plainによるクロスオリジンのPOSTを防ぐ）
+fn is_json_content_type(request: &hyper::Request<hyper::Body>) -> bool {
+    request
+        .headers()
+        .get(hyper::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(';').next().unwrap_or_default().trim().eq_ignore_ascii_case("application/json"))
+        .unwrap_or(false)
+}
+
+// リクエスト本文を上限まで読み込む（上限を超えた場合はNone）
+async fn read_body(mut body: hyper::Body, limit: usize) -> hyper::Result<Option<Vec<u8>>> {
+    let mut buffer = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if buffer.len() + chunk.len() > limit {
+            return Ok(None);
+        }
+        buffer.extend_from_slice(&chunk);
+    }
+    Ok(Some(buffer))
+}
+
+// JSON-RPCのPOSTリクエストを処理し、受信した本文とバッチの各呼び出しをログエントリに追加
+async fn handle_rpc_request(io: RpcHandler, request: hyper::Request<hyper::Body>, mut meta: RpcMeta, entry: LogEntry, max_body_bytes: usize) -> hyper::Result<(HttpReply, LogEntry)> {
+    let body = match read_body(request.into_body(), max_body_bytes).await? {
+        Some(body) => String::from_utf8_lossy(&body).into_owned(),
+        None => {
+            return Ok((HttpReply::text(hyper::StatusCode::PAYLOAD_TOO_LARGE, "Request body is too large"), entry));
+        }
+    };
+
+    let entry = entry.with_body(&body);
+    let (entry, batch) = match serde_json::from_str::<jsonrpc_core::Request>(&body) {
+        Ok(call) => {
+            let batch = matches!(call, jsonrpc_core::Request::Batch(_)).then(|| Arc::new(BatchLog::default()));
+            (entry.with_call(&call), batch)
+        }
+        Err(_) => (entry, None),
+    };
+    meta.batch = batch.clone();
+
+    // 通知（idなし）のみの場合はレスポンス本文が空になる
+    let response = io.handle_request(&body, meta).await;
+    let reply = HttpReply::json(response.map(|response| format!("{}\n", response)).unwrap_or_default());
+    let entry = match batch {
+        Some(batch) => entry.with_batch(&batch),
+        None => entry,
+    };
+    Ok((reply, entry))
 }
 
 // RequestMiddlewareトレイトの実装
 impl RequestMiddleware for LoggingMiddleware {
     fn on_request(&self, request: hyper::Request<hyper::Body>) -> RequestMiddlewareAction {
+        let started = Instant::now();
+
+        // ログのリクエストIDは常にサーバーで生成する（クライアントが指定した X-Request-Id は別に記録して返す）
+        let ids = RequestIds {
+            id: new_request_id(),
+            client_id: request
+                .headers()
+                .get(REQUEST_ID_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(sanitize_request_id),
+        };
+
+        // CORSプリフライトはサーバーに任せる（Authorizationヘッダーは送られない）
+        if request.method() == hyper::Method::OPTIONS {
+            return RequestMiddlewareAction::Proceed {
+                should_continue_on_invalid_cors: false,
+                request,
+            };
+        }
+
+        // 死活監視は認証なしで応答する
+        if request.method() == hyper::Method::GET {
+            match request.uri().path() {
+                HEALTH_PATH => return health_reply().into_response(&ids, None).into(),
+                READY_PATH => {
+                    let reply = ready_reply(self.note_store.as_deref(), self.drain.is_draining());
+                    return reply.into_response(&ids, None).into();
+                }
+                _ => {}
+            }
+        }
+
         // リクエストの主要部分を取得
         let method = request.method().to_string();
         let uri = request.uri().to_string();
         
-        // ヘッダーを文字列化
-        let headers = {
-            let mut header_str = String::new();
-            for (key, value) in request.headers() {
-                header_str.push_str(&format!("{}: {:?}, ", key, value));
+        // ヘッダーを受信した順に記録
+        let headers = request
+            .headers()
+            .iter()
+            .map(|(name, value)| LogHeader::new(name.as_str(), String::from_utf8_lossy(value.as_bytes())))
+            .collect();
+        
+        // 認証チェック
+        let authorization = request
+            .headers()
+            .get(hyper::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok());
+
+        let auth_context = match self.auth.verify(authorization) {
+            Ok(auth_context) => auth_context,
+            Err(error) => {
+                let entry = LogEntry::new(ids.id.clone(), method, uri, headers, None)
+                    .with_scheme(self.scheme)
+                    .with_client_request_id(ids.client_id.clone());
+                self.logger.log_rejected(entry, 401, error.to_string());
+                return unauthorized_response(error, &ids).into();
             }
-            header_str
         };
-        
-        // Bodyはこのミドルウェアでは取得できないため、Noneを設定
-        self.logger.log_request(method, uri, headers, None);
-        
-        // 通常の処理を続行
-        RequestMiddlewareAction::Proceed { 
-            should_continue_on_invalid_cors: false,
-            request 
+
+        let origin = request
+            .headers()
+            .get(hyper::header::ORIGIN)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        let entry = LogEntry::new(ids.id.clone(), method, uri, headers, auth_context.token_id.clone())
+            .with_scheme(self.scheme)
+            .with_origin(origin)
+            .with_client_request_id(ids.client_id.clone());
+
+        let cors_origin = match cors_allow_origin(&request, &self.cors_domains) {
+            AllowCors::Ok(origin) => Some(origin),
+            AllowCors::NotRequired => None,
+            AllowCors::Invalid => {
+                let reply = HttpReply::text(hyper::StatusCode::FORBIDDEN, "Origin of the request is not allowed");
+                return reply.finish(&self.logger, &ids, entry, started, None).into();
+            }
+        };
+
+        // GET /metrics: Prometheusのテキスト形式でメトリクスを返す（ログには記録しない）
+        if request.method() == hyper::Method::GET && request.uri().path() == METRICS_PATH {
+            let gauges = Gauges {
+                notes: self.note_store.as_ref().map(|store| store.count()),
+                log_entries: self.logger.get_logs().len(),
+            };
+            return HttpReply::metrics(self.metrics.render(&gauges))
+                .into_response(&ids, cors_origin)
+                .into();
+        }
+
+        // GET /openrpc.json: OpenRPCドキュメントを返す
+        if request.method() == hyper::Method::GET && request.uri().path() == OPENRPC_PATH {
+            let reply = HttpReply::json(self.openrpc_document.to_string());
+            return reply.finish(&self.logger, &ids, entry, started, cors_origin).into();
+        }
+
+        if request.method() != hyper::Method::POST {
+            let reply = HttpReply::text(hyper::StatusCode::METHOD_NOT_ALLOWED, "Used HTTP Method is not allowed. POST or OPTIONS is required");
+            return reply.finish(&self.logger, &ids, entry, started, cors_origin).into();
+        }
+
+        if !is_json_content_type(&request) {
+            let reply = HttpReply::text(
+                hyper::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "Supplied content type is not allowed. Content-Type: application/json is required",
+            );
+            return reply.finish(&self.logger, &ids, entry, started, cors_origin).into();
+        }
+
+        // 停止処理中は新しいリクエストを受け付けない（受け付けたリクエストはレスポンスを返すまで数える）
+        let Some(drain_guard) = self.drain.enter() else {
+            self.metrics.observe_rejection("shutting_down");
+            let reply = HttpReply::text(hyper::StatusCode::SERVICE_UNAVAILABLE, "Server is shutting down");
+            return reply.finish(&self.logger, &ids, entry, started, cors_origin).into();
+        };
+
+        // クライアントごとのリクエスト数と同時処理数を制限
+        if let Some(rate_limiter) = &self.rate_limiter {
+            let peer = request.extensions().get::<PeerAddr>().map(|peer| peer.0.ip());
+            let client = client_key(auth_context.token_id.as_deref(), peer, entry.origin.as_deref());
+            if let Err(retry_after) = rate_limiter.check(&client, Instant::now()) {
+                self.metrics.observe_rejection("rate_limited");
+                let reply = rate_limited_reply("rate", retry_after, &ids.id);
+                return reply.finish(&self.logger, &ids, entry, started, cors_origin).into();
+            }
+        }
+        let permit = match &self.concurrency_limit {
+            Some(limit) => match limit.try_acquire() {
+                Some(permit) => Some(permit),
+                None => {
+                    self.metrics.observe_rejection("in_flight");
+                    let retry_after = Duration::from_secs(IN_FLIGHT_RETRY_AFTER_SECS);
+                    let reply = rate_limited_reply("in_flight", retry_after, &ids.id);
+                    return reply.finish(&self.logger, &ids, entry, started, cors_origin).into();
+                }
+            },
+            None => None,
+        };
+
+        let meta = RpcMeta {
+            auth: auth_context,
+            request_id: Some(ids.id.clone()),
+            ..RpcMeta::default()
+        };
+        let io = self.io.clone();
+        let logger = self.logger.clone();
+        let metrics = self.metrics.clone();
+        let max_body_bytes = self.max_body_bytes;
+        logger.started(&entry);
+
+        RequestMiddlewareAction::Respond {
+            should_validate_hosts: true,
+            response: Box::pin(async move {
+                // レスポンスを返すまで同時処理数の枠と停止処理の待機対象を保持する
+                let _permit = permit;
+                let _drain_guard = drain_guard;
+                let (reply, entry) = handle_rpc_request(io, request, meta, entry, max_body_bytes).await?;
+                if reply.status == hyper::StatusCode::PAYLOAD_TOO_LARGE {
+                    metrics.observe_rejection("body_too_large");
+                }
+                Ok(reply.finish(&logger, &ids, entry, started, cors_origin))
+            }),
         }
     }
 }
 
+// WebSocketハンドシェイク時のBearerトークン認証とログ記録
+struct WsAuthMiddleware {
+    logger: Arc<Logger>,
+    auth: Arc<TokenAuth>,
+    // 認証結果とリソースパスをメタデータ抽出に渡すためのスロット
+    // ハンドシェイクは1つのイベントループで順に処理され、ミドルウェアの直後にメタデータが抽出される
+    pending: Arc<Mutex<Option<(AuthContext, String)>>>,
+    drain: Arc<Drain>,
+}
+
+// WebSocketのハンドシェイクを拒否するレスポンス
+fn ws_unauthorized_response(error: AuthError) -> ws_server::ws::Response {
+    let mut response = ws_server::ws::Response::new(401, "Unauthorized", format!("{}\n", error).into_bytes());
+    response
+        .headers_mut()
+        .push(("WWW-Authenticate".to_string(), b"Bearer".to_vec()));
+    response
+}
+
+// ws://host:port/?access_token=<token> 形式のトークンを取り出す
+fn query_access_token(resource: &str) -> Option<&str> {
+    let (_, query) = resource.split_once('?')?;
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("access_token="))
+        .filter(|token| !token.is_empty())
+}
+
+impl ws_server::RequestMiddleware for WsAuthMiddleware {
+    fn process(&self, request: &ws_server::ws::Request) -> ws_server::MiddlewareAction {
+        let uri = request.resource().to_string();
+
+        // ヘッダーを受信した順に記録
+        let headers = request
+            .headers()
+            .iter()
+            .map(|(name, value)| LogHeader::new(name.as_str(), String::from_utf8_lossy(value)))
+            .collect();
+
+        // ブラウザのWebSocket APIはヘッダーを設定できないため、クエリパラメータのトークンも受け付ける
+        let authorization = request
+            .header("authorization")
+            .and_then(|value| std::str::from_utf8(value).ok())
+            .map(|value| value.to_string())
+            .or_else(|| query_access_token(&uri).map(|token| format!("Bearer {}", token)));
+
+        // 停止処理中は新しい接続を受け付けない
+        if self.drain.is_draining() {
+            *self.pending.lock().unwrap() = None;
+            let entry = LogEntry::new(new_request_id(), "WS".to_string(), uri, headers, None);
+            self.logger.log_rejected(entry, 503, "Server is shutting down".to_string());
+            let response = ws_server::ws::Response::new(503, "Service Unavailable", b"Server is shutting down\n".to_vec());
+            return Some(response).into();
+        }
+
+        let result = self.auth.verify(authorization.as_deref());
+        *self.pending.lock().unwrap() = result.clone().ok().map(|auth| (auth, uri.clone()));
+
+        match result {
+            Ok(auth_context) => {
+                // ハンドシェイク（101 Switching Protocols）を記録。メッセージは TransportLogMiddleware で記録する
+                let origin = request
+                    .header("origin")
+                    .map(|value| String::from_utf8_lossy(value).into_owned());
+                self.logger.record(
+                    LogEntry::new(new_request_id(), "WS".to_string(), uri, headers, auth_context.token_id)
+                        .with_origin(origin)
+                        .with_status(101),
+                );
+                ws_server::MiddlewareAction::Proceed
+            }
+            Err(error) => {
+                let entry = LogEntry::new(new_request_id(), "WS".to_string(), uri, headers, None);
+                self.logger.log_rejected(entry, 401, error.to_string());
+                Some(ws_unauthorized_response(error)).into()
+            }
+        }
+    }
+}
+
+// jsonrpc-ipc-server はバインドする前に既存のパスを削除するため、
+// ソケット以外のファイルと、他のプロセスが待ち受けているソケットは使わない
+#[cfg(unix)]
+fn check_ipc_path(path: &str) -> Result<(), String> {
+    use std::os::unix::fs::FileTypeExt;
+
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("Failed to inspect IPC path {}: {}", path, e)),
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(format!("IPC path {} already exists and is not a socket", path));
+    }
+    // 接続できる場合は起動中の別のインスタンスが使っている（接続できなければ前回の残り）
+    if std::os::unix::net::UnixStream::connect(path).is_ok() {
+        return Err(format!("IPC socket {} is already in use", path));
+    }
+    Ok(())
+}
+
+// Windowsの名前付きパイプはファイルを削除しない
+#[cfg(not(unix))]
+fn check_ipc_path(_path: &str) -> Result<(), String> {
+    Ok(())
+}
+
+// WebSocketサーバーの起動エラーがポート使用中によるものか
+fn is_ws_addr_in_use(error: &ws_server::Error) -> bool {
+    match error {
+        ws_server::Error::Io(e) => e.kind() == io::ErrorKind::AddrInUse,
+        ws_server::Error::WsError(e) => {
+            matches!(&e.kind, ws_server::ws::ErrorKind::Io(e) if e.kind() == io::ErrorKind::AddrInUse)
+        }
+        _ => false,
+    }
+}
+
+// HTTPリスナーで接続ごとに使う jsonrpc-http-server のハンドラー
+type HttpHandler = ServerHandler<RpcMeta, RpcMiddleware>;
+
+// 接続元のアドレス（リクエストの拡張として LoggingMiddleware に渡す）
+#[derive(Debug, Clone, Copy)]
+struct PeerAddr(SocketAddr);
+
+// HTTP・HTTPSリスナー（HTTPSの場合もこのプロセス内でTLSを終端し、平文のリスナーは開かない）
+struct HttpListener {
+    addr: SocketAddr,
+    // HTTPSの場合の証明書のフィンガープリント
+    fingerprint: Option<String>,
+    // 接続を受け付けるタスク（中止するとリスナーが閉じる）
+    accept_task: tokio::task::JoinHandle<()>,
+    runtime: Option<tokio::runtime::Runtime>,
+}
+
+impl HttpListener {
+    fn address(&self) -> SocketAddr {
+        self.addr
+    }
+
+    fn fingerprint(&self) -> Option<&str> {
+        self.fingerprint.as_deref()
+    }
+
+    // 新しい接続の受け付けを止める（受け付けた接続のリクエストはそのまま処理する）
+    fn stop_accepting(&self) {
+        self.accept_task.abort();
+    }
+
+    // 待ち受けを終了し、処理中の接続を閉じる
+    fn close(self) {
+        drop(self);
+    }
+}
+
+impl Drop for HttpListener {
+    fn drop(&mut self) {
+        // ランタイムを止めて残った接続を閉じる
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+// 受け付けた接続をHTTP/1.1で処理する（HTTPSの場合は先にTLSのハンドシェイクを行う）
+async fn serve_connection(
+    stream: tokio::net::TcpStream,
+    peer: SocketAddr,
+    acceptor: Option<TlsAcceptor>,
+    mut handler: HttpHandler,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    stream.set_nodelay(true)?;
+    let service = hyper::service::service_fn(move |mut request: hyper::Request<hyper::Body>| {
+        request.extensions_mut().insert(PeerAddr(peer));
+        handler.call(request)
+    });
+
+    let http = hyper::server::conn::Http::new();
+    match acceptor {
+        Some(acceptor) => http.serve_connection(acceptor.accept(stream).await?, service).await?,
+        None => http.serve_connection(stream, service).await?,
+    }
+    Ok(())
+}
+
+// 停止処理中に処理中のリクエストの完了を待つ（サーバーのロックを保持せずに待てるよう分けている）
+pub struct PendingStop {
+    drain: Arc<Drain>,
+    timeout: Duration,
+}
+
+impl PendingStop {
+    // 時間内に終わらなかったリクエストの数を返す
+    pub fn wait(&self) -> usize {
+        self.drain.drain(self.timeout)
+    }
+}
+
 pub struct JsonRpcServer {
-    server: Option<Server>,
+    server: Option<HttpListener>,
     running: bool,
     logger: Arc<Logger>,
+    auth: Arc<TokenAuth>,
     app_handle: Option<AppHandle>,
+    // 証明書・IPCソケット・エンドポイントファイル・ログの保存先
+    app_data_dir: Option<PathBuf>,
+    // GET /ready とメモのRPCメソッドで使う（ない場合はメモのメソッドが使えない）
+    note_store: Option<Arc<NoteStore>>,
+    config: ServerConfig,
+    // 実際にバインドしたアドレス（起動中のみ）
+    bound_addr: Option<SocketAddr>,
+    ws_server: Option<ws_server::Server>,
+    ws_bound_addr: Option<SocketAddr>,
+    ipc_server: Option<ipc_server::Server>,
+    // IPCソケットのパス（起動中のみ）
+    ipc_path: Option<String>,
+    subscriptions: Arc<NoteSubscriptions>,
+    // NoteStoreに登録した変更リスナーのID（起動中のみ）
+    note_listener_id: Option<u64>,
+    // すべてのトランスポートで共有するRPCハンドラー（起動中のみ。ログの再実行に使う）
+    io: Option<RpcHandler>,
+    // 再起動しても引き継ぐ
+    metrics: Arc<Metrics>,
+    // 停止時に処理中のリクエストの完了を待つための状態（起動ごとに作り直す）
+    drain: Arc<Drain>,
+    // 停止処理が始まってから終わるまで true（begin_stop と finish_stop の間）
+    stopping: bool,
+    // HTTPリスナーで候補ポートより先に試すポート（前回起動したポート）
+    preferred_port: Option<u16>,
 }
 
 impl JsonRpcServer {
@@ -150,415 +738,615 @@ impl JsonRpcServer {
             server: None,
             running: false,
             logger: Arc::new(Logger::new()),
+            auth: Arc::new(TokenAuth::disabled()),
             app_handle: None,
+            app_data_dir: None,
+            note_store: None,
+            config: ServerConfig::default(),
+            bound_addr: None,
+            ws_server: None,
+            ws_bound_addr: None,
+            ipc_server: None,
+            ipc_path: None,
+            subscriptions: Arc::new(NoteSubscriptions::default()),
+            note_listener_id: None,
+            io: None,
+            metrics: Arc::new(Metrics::default()),
+            drain: Arc::new(Drain::default()),
+            stopping: false,
+            preferred_port: None,
         }
     }
 
+    // アプリのデータディレクトリも設定される
     pub fn set_app_handle(&mut self, app_handle: AppHandle) {
+        // ログの追加・削除をフロントエンドに通知
+        let handle = app_handle.clone();
+        self.set_log_listener(Box::new(move |event| {
+            let result = match event {
+                LogEvent::Appended(entries) => handle.emit(EVENT_LOG_APPENDED, entries),
+                LogEvent::Cleared => handle.emit(EVENT_LOGS_CLEARED, ()),
+            };
+            if let Err(e) = result {
+                eprintln!("Failed to emit log event: {}", e);
+            }
+        }));
+
+        if let Ok(app_data_dir) = app_handle.path().app_data_dir() {
+            self.app_data_dir = Some(app_data_dir);
+        }
         self.app_handle = Some(app_handle);
     }
 
+    // AppHandleを使わずに起動する場合（ヘッドレスモード）のデータディレクトリ
+    pub fn set_app_data_dir(&mut self, app_data_dir: PathBuf) {
+        self.app_data_dir = Some(app_data_dir);
+        self.configure_logger();
+    }
+
+    fn app_data_dir(&self) -> Result<&Path, Box<dyn std::error::Error>> {
+        self.app_data_dir
+            .as_deref()
+            .ok_or_else(|| "App data directory is not set".into())
+    }
+
+    pub fn set_note_store(&mut self, note_store: Arc<NoteStore>) {
+        self.note_store = Some(note_store);
+    }
+
+    // ログの追加・削除の通知先
+    pub fn set_log_listener(&self, listener: LogListener) {
+        self.logger.set_listener(listener);
+    }
+
+    // 完了したリクエストのログを取りこぼさずに受け取るチャネル（UI向けの通知と違い、開始の通知や間引きはない）
+    pub fn subscribe_completed_logs(&self) -> mpsc::Receiver<LogEntry> {
+        self.logger.subscribe_completed()
+    }
+
+    // 認証設定は起動中のミドルウェアと共有されるため、変更は即座に反映される
+    pub fn set_auth(&mut self, auth: TokenAuth) {
+        self.auth = Arc::new(auth);
+    }
+
+    pub fn auth(&self) -> Arc<TokenAuth> {
+        self.auth.clone()
+    }
+
+    pub fn config(&self) -> &ServerConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: ServerConfig) {
+        self.config = config;
+        self.configure_logger();
+    }
+
+    pub fn set_preferred_port(&mut self, port: Option<u16>) {
+        self.preferred_port = port;
+    }
+
+    // HTTPリスナーで順に試すポートの一覧（前回起動したポートを先頭に追加）
+    fn http_candidate_ports(&self) -> Vec<u16> {
+        let mut ports = self.config.candidate_ports();
+        if let Some(port) = self.preferred_port {
+            let ws_conflict = self.config.ws_enabled && port == self.config.ws_port;
+            if port != 0 && !ws_conflict {
+                ports.retain(|candidate| *candidate != port);
+                ports.insert(0, port);
+            }
+        }
+        ports
+    }
+
+    // ログの保持件数とファイルへの保存の設定を反映（停止中も記録されたログに適用される）
+    fn configure_logger(&self) {
+        let log_dir = self.app_data_dir.as_ref().map(|dir| dir.join(LOG_DIR_NAME));
+        self.logger.configure(&self.config, log_dir.as_deref());
+    }
+
+    // 設定を変更し、サーバーが起動中であれば新しい設定で再起動する
+    pub fn update_config(&mut self, config: ServerConfig) -> Result<(), Box<dyn std::error::Error>> {
+        config.validate()?;
+
+        let was_running = self.running;
+        if was_running {
+            self.stop()?;
+        }
+
+        // ポートの設定を変更した場合は前回のポートを使わない
+        if config.port != self.config.port || config.port_range_end != self.config.port_range_end {
+            self.preferred_port = None;
+            if let Err(e) = self.save_last_port(None) {
+                eprintln!("Failed to save server settings: {}", e);
+            }
+        }
+
+        self.config = config;
+        self.configure_logger();
+
+        if was_running {
+            self.start()?;
+        }
+
+        Ok(())
+    }
+
+    // 起動中は実際にバインドしたアドレス、停止中は設定上のアドレスを返す
+    pub fn url(&self) -> String {
+        match self.bound_addr {
+            Some(addr) => format!("{}://{}", self.config.scheme(), addr),
+            None => self.config.url(),
+        }
+    }
+
+    // IPCソケットのパス（起動中のみ）
+    pub fn ipc_path(&self) -> Option<String> {
+        self.ipc_path.clone()
+    }
+
+    // HTTPSの証明書のフィンガープリント（HTTPSが無効な場合はNone）
+    // 停止中は次の起動で使う証明書を読み込む（自己署名証明書がなければ生成する）
+    pub fn tls_fingerprint(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        if let Some(fingerprint) = self.server.as_ref().and_then(|server| server.fingerprint()) {
+            return Ok(Some(fingerprint.to_string()));
+        }
+        if !self.config.tls_enabled {
+            return Ok(None);
+        }
+
+        Ok(Some(TlsIdentity::load_or_generate(&self.config, self.app_data_dir()?)?.fingerprint()))
+    }
+
+    // WebSocketリスナーのURL（無効な場合はNone）
+    pub fn ws_url(&self) -> Option<String> {
+        if !self.config.ws_enabled {
+            return None;
+        }
+
+        let addr = match self.ws_bound_addr {
+            Some(addr) => addr,
+            None => self.config.socket_addr_with_port(self.config.ws_port).ok()?,
+        };
+        Some(format!("ws://{}", addr))
+    }
+
     pub fn start(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         if self.running {
-            return Err("Server is already running".into());
+            return Err(AppError::Unavailable("Server is already running".to_string()).into());
         }
 
-        let app_handle = match &self.app_handle {
-            Some(handle) => handle.clone(),
-            None => return Err("App handle is not set".into()),
-        };
+        let app_data_dir = self.app_data_dir()?.to_path_buf();
 
-        let mut io = IoHandler::default();
-        
-        // Echo method - オブジェクトパラメータに対応
-        let logger_clone = self.logger.clone();
-        io.add_method("echo", move |params: Params| {
-            let logger = logger_clone.clone();
-            async move {
-                // パラメータの文字列表現を取得してログに追加
-                if let Ok(params_str) = serde_json::to_string(&params) {
-                    // 直近のログエントリのbodyフィールドを更新
-                    let mut logs = logger.logs.lock().unwrap();
-                    if let Some(last_entry) = logs.back_mut() {
-                        last_entry.body = Some(params_str.clone());
-                    }
-                }
-                
-                // 構造体へのデシリアライズまたは後方互換性のある方法でパラメータを取得
-                let message = match params {
-                    // 新しいオブジェクト形式
-                    Params::Map(map) => {
-                        match map.get("message") {
-                            Some(Value::String(s)) => s.clone(),
-                            _ => return Err(Error::invalid_params("Missing or invalid 'message' parameter")),
-                        }
-                    },
-                    // 後方互換性のための配列形式サポート
-                    Params::Array(array) => {
-                        match array.get(0) {
-                            Some(Value::String(s)) => s.clone(),
-                            _ => return Err(Error::invalid_params("Expected string parameter")),
-                        }
-                    },
-                    _ => return Err(Error::invalid_params("Expected object or array params")),
-                };
-                
-                Ok(Value::String(message))
-            }
+        self.drain = Arc::new(Drain::default());
+
+        // すべてのトランスポートで共有するRPCハンドラー
+        let note_store = self.note_store.clone();
+        let io = build_io_handler(RpcContext {
+            note_store: note_store.clone(),
+            logger: self.logger.clone(),
+            config: self.config.clone(),
+            subscriptions: self.subscriptions.clone(),
+            metrics: self.metrics.clone(),
+            drain: self.drain.clone(),
         });
 
-        // System info method - パラメータはないのでそのまま
-        let logger_clone = self.logger.clone();
-        io.add_method("system_info", move |params: Params| {
-            let logger = logger_clone.clone();
-            async move {
-                // パラメータの文字列表現を取得してログに追加
-                if let Ok(params_str) = serde_json::to_string(&params) {
-                    // 直近のログエントリのbodyフィールドを更新
-                    let mut logs = logger.logs.lock().unwrap();
-                    if let Some(last_entry) = logs.back_mut() {
-                        last_entry.body = Some(params_str.clone());
+        // 有効なトランスポートを順に起動（失敗した場合は起動済みのものを閉じる）
+        let server = if self.config.http_enabled {
+            Some(self.start_http(io.clone(), note_store.clone(), &app_data_dir)?)
+        } else {
+            None
+        };
+
+        // 同じメソッドを提供するWebSocketリスナーを起動
+        let ws_server = if self.config.ws_enabled {
+            match self.start_ws(io.clone()) {
+                Ok(ws_server) => Some(ws_server),
+                Err(e) => {
+                    if let Some(server) = server {
+                        server.close();
                     }
+                    return Err(e);
                 }
-                
-                let system_info = SystemInfo {
-                    app_name: env!("CARGO_PKG_NAME").to_string(),
-                    version: env!("CARGO_PKG_VERSION").to_string(),
-                    os: env::consts::OS.to_string(),
-                    arch: env::consts::ARCH.to_string(),
-                };
-                
-                Ok(serde_json::to_value(system_info).unwrap())
             }
-        });
+        } else {
+            None
+        };
 
-        // notes_list: すべてのメモをリスト表示 - パラメータはないのでそのまま
-        let app_handle_clone = app_handle.clone();
-        let logger_clone = self.logger.clone();
-        io.add_method("notes_list", move |params: Params| {
-            let logger = logger_clone.clone();
-            let app_handle = app_handle_clone.clone();
-            
-            async move {
-                // パラメータの文字列表現を取得してログに追加
-                if let Ok(params_str) = serde_json::to_string(&params) {
-                    let mut logs = logger.logs.lock().unwrap();
-                    if let Some(last_entry) = logs.back_mut() {
-                        last_entry.body = Some(params_str);
+        // ローカル自動化向けのIPCソケットを起動
+        let ipc_path = if self.config.ipc_enabled {
+            Some(self.config.ipc_endpoint(&app_data_dir))
+        } else {
+            None
+        };
+        let ipc_server = match &ipc_path {
+            Some(path) => match self.start_ipc(io.clone(), path) {
+                Ok(ipc_server) => Some(ipc_server),
+                Err(e) => {
+                    if let Some(server) = server {
+                        server.close();
                     }
+                    if let Some(ws_server) = ws_server {
+                        ws_server.close();
+                    }
+                    return Err(e);
                 }
-                
-                // NoteStoreを取得
-                let note_store = match app_handle.try_state::<crate::NotesState>() {
-                    Some(state) => state.0.clone(),
-                    None => return Err(Error::internal_error()),
-                };
-                
-                // すべてのメモを取得
-                let notes = note_store.list();
-                
-                // メモリストをJSONに変換して返す
-                match serde_json::to_value(notes) {
-                    Ok(value) => Ok(value),
-                    Err(_) => Err(Error::internal_error()),
-                }
+            },
+            None => None,
+        };
+
+        // メモの変更を購読者に転送
+        if let Some(note_store) = &note_store {
+            let subscriptions = self.subscriptions.clone();
+            let listener_id = note_store.add_change_listener(Box::new(move |change| subscriptions.notify(change)));
+            self.note_listener_id = Some(listener_id);
+        }
+
+        self.bound_addr = server.as_ref().map(|server| server.address());
+        self.server = server;
+        self.ws_bound_addr = ws_server.as_ref().map(|ws_server| *ws_server.addr());
+        self.ws_server = ws_server;
+        self.ipc_server = ipc_server;
+        self.ipc_path = ipc_path;
+        self.io = Some(io);
+        self.running = true;
+
+        // 実際のURLをフロントエンドに通知
+        let payload = ServerStartedPayload {
+            url: self.bound_addr.map(|_| self.url()),
+            port: self.bound_addr.map(|addr| addr.port()),
+            ws_url: self.ws_url(),
+            ipc_path: self.ipc_path.clone(),
+            tls_fingerprint: self.server.as_ref().and_then(|server| server.fingerprint()).map(str::to_string),
+        };
+        if let Err(e) = self.write_endpoint_file(&payload) {
+            eprintln!("Failed to write server endpoint file: {}", e);
+        }
+        // 次回の起動時・再起動時も同じポートを使う（HTTPが無効な場合は前回の値を残す）
+        if let Some(port) = payload.port {
+            self.preferred_port = Some(port);
+            if let Err(e) = self.save_last_port(Some(port)) {
+                eprintln!("Failed to save server settings: {}", e);
             }
-        });
+        }
+        self.emit(EVENT_SERVER_STARTED, payload);
 
-        // notes_get: 指定されたIDのメモを取得 - オブジェクトパラメータに対応
-        let app_handle_clone = app_handle.clone();
-        let logger_clone = self.logger.clone();
-        io.add_method("notes_get", move |params: Params| {
-            let logger = logger_clone.clone();
-            let app_handle = app_handle_clone.clone();
-            
-            async move {
-                // パラメータの文字列表現を取得してログに追加
-                if let Ok(params_str) = serde_json::to_string(&params) {
-                    let mut logs = logger.logs.lock().unwrap();
-                    if let Some(last_entry) = logs.back_mut() {
-                        last_entry.body = Some(params_str);
-                    }
+        Ok(())
+    }
+
+    // HTTPリスナーを起動（候補ポートを順に試し、使用中であれば次のポートへフォールバック）
+    // HTTPSの場合も同じリスナーでTLSを終端する
+    fn start_http(&self, io: RpcHandler, note_store: Option<Arc<NoteStore>>, app_data_dir: &Path) -> Result<HttpListener, Box<dyn std::error::Error>> {
+        // 証明書の読み込み・生成に失敗した場合はポートを開かない
+        let identity = if self.config.tls_enabled {
+            Some(TlsIdentity::load_or_generate(&self.config, app_data_dir)?)
+        } else {
+            None
+        };
+        let listener_name = if identity.is_some() { "HTTPS listener" } else { "JSON-RPC server" };
+        let mut last_error: Option<io::Error> = None;
+
+        for port in self.http_candidate_ports() {
+            let addr = self.config.socket_addr_with_port(port)?;
+
+            match TcpListener::bind(addr) {
+                Ok(listener) => {
+                    return self
+                        .http_server(io, note_store, listener, identity.as_ref())
+                        .map_err(|e| format!("Failed to start {}: {}", listener_name, e).into());
                 }
-                
-                // パラメータからIDを取得
-                let id = match params {
-                    // 新しいオブジェクト形式
-                    Params::Map(map) => {
-                        match map.get("id") {
-                            Some(Value::String(s)) => s.clone(),
-                            _ => return Err(Error::invalid_params("Missing or invalid 'id' parameter")),
-                        }
-                    },
-                    // 後方互換性のための配列形式サポート
-                    Params::Array(array) => {
-                        match array.get(0) {
-                            Some(Value::String(s)) => s.clone(),
-                            _ => return Err(Error::invalid_params("Expected string parameter for note ID")),
-                        }
-                    },
-                    _ => return Err(Error::invalid_params("Expected object or array params")),
-                };
-                
-                // NoteStoreを取得
-                let note_store = match app_handle.try_state::<crate::NotesState>() {
-                    Some(state) => state.0.clone(),
-                    None => return Err(Error::internal_error()),
-                };
-                
-                // メモを取得
-                match note_store.get(&id) {
-                    Some(note) => match serde_json::to_value(note) {
-                        Ok(value) => Ok(value),
-                        Err(_) => Err(Error::internal_error()),
-                    },
-                    None => Ok(Value::Null),
+                Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+                    eprintln!("Port {} is already in use, trying next candidate", port);
+                    last_error = Some(e);
                 }
+                Err(e) => return Err(format!("Failed to start {}: {}", listener_name, e).into()),
             }
+        }
+
+        let reason = last_error.map(|e| e.to_string()).unwrap_or_default();
+        Err(AppError::Unavailable(format!("Failed to start {}: no available port ({})", listener_name, reason)).into())
+    }
+
+    // バインドしたリスナーでJSON-RPCのHTTPサーバーを起動（identity がある場合はHTTPS）
+    fn http_server(
+        &self,
+        io: RpcHandler,
+        note_store: Option<Arc<NoteStore>>,
+        listener: TcpListener,
+        identity: Option<&TlsIdentity>,
+    ) -> Result<HttpListener, Box<dyn std::error::Error>> {
+        // 設定からCORSの許可オリジンを構築
+        let allowed_origins: Vec<AccessControlAllowOrigin> = self
+            .config
+            .allowed_origins
+            .iter()
+            .map(|origin| origin.as_str().into())
+            .collect();
+
+        let rate_limiter = self.config.rate_limit_per_sec.map(|per_sec| {
+            let burst = self.config.rate_limit_burst.unwrap_or(per_sec);
+            Arc::new(RateLimiter::new(per_sec, burst))
         });
 
-        // notes_create: 新しいメモを作成 - オブジェクトパラメータに対応
-        let app_handle_clone = app_handle.clone();
-        let logger_clone = self.logger.clone();
-        io.add_method("notes_create", move |params: Params| {
-            let logger = logger_clone.clone();
-            let app_handle = app_handle_clone.clone();
-            
-            async move {
-                // パラメータの文字列表現を取得してログに追加
-                if let Ok(params_str) = serde_json::to_string(&params) {
-                    let mut logs = logger.logs.lock().unwrap();
-                    if let Some(last_entry) = logs.back_mut() {
-                        last_entry.body = Some(params_str);
-                    }
-                }
-                
-                // パラメータからタイトルと内容を取得
-                let (title, content) = match params {
-                    // 新しいオブジェクト形式
-                    Params::Map(map) => {
-                        let title = match map.get("title") {
-                            Some(Value::String(s)) => s.clone(),
-                            _ => return Err(Error::invalid_params("Missing or invalid 'title' parameter")),
-                        };
-                        
-                        let content = match map.get("content") {
-                            Some(Value::String(s)) => s.clone(),
-                            _ => return Err(Error::invalid_params("Missing or invalid 'content' parameter")),
-                        };
-                        
-                        (title, content)
-                    },
-                    // 後方互換性のための配列形式サポート
-                    Params::Array(array) => {
-                        if array.len() < 2 {
-                            return Err(Error::invalid_params("Expected title and content parameters"));
-                        }
-                        
-                        let title = match array.get(0) {
-                            Some(Value::String(s)) => s.clone(),
-                            _ => return Err(Error::invalid_params("Expected string parameter for title")),
-                        };
-                        
-                        let content = match array.get(1) {
-                            Some(Value::String(s)) => s.clone(),
-                            _ => return Err(Error::invalid_params("Expected string parameter for content")),
-                        };
-                        
-                        (title, content)
-                    },
-                    _ => return Err(Error::invalid_params("Expected object or array params")),
-                };
-                
-                // NoteStoreを取得
-                let note_store = match app_handle.try_state::<crate::NotesState>() {
-                    Some(state) => state.0.clone(),
-                    None => return Err(Error::internal_error()),
-                };
-                
-                // メモを作成
-                match note_store.create(title, content) {
-                    Ok(note) => match serde_json::to_value(note) {
-                        Ok(value) => Ok(value),
-                        Err(_) => Err(Error::internal_error()),
-                    },
-                    Err(_) => Err(Error::internal_error()),
-                }
-            }
+        // ミドルウェアの準備
+        let middleware: Arc<dyn RequestMiddleware> = Arc::new(LoggingMiddleware {
+            logger: self.logger.clone(),
+            auth: self.auth.clone(),
+            io: io.clone(),
+            cors_domains: Some(allowed_origins.clone()),
+            openrpc_document: Arc::new(openrpc::document(&self.config).to_string()),
+            metrics: self.metrics.clone(),
+            note_store,
+            max_body_bytes: self.config.max_request_body_bytes,
+            rate_limiter,
+            concurrency_limit: self.config.max_in_flight_requests.map(|max| Arc::new(ConcurrencyLimit::new(max))),
+            drain: self.drain.clone(),
+            scheme: if identity.is_some() { "https" } else { "http" },
         });
+        let acceptor = identity.map(|identity| identity.acceptor()).transpose()?;
+        let fingerprint = identity.map(|identity| identity.fingerprint());
+
+        // JSON-RPCのリクエストはミドルウェアで処理するため、ハンドラーにはCORSプリフライトの処理のみが残る
+        let rpc = Rpc {
+            handler: Arc::new(io),
+            extractor: Arc::new(|_: &hyper::Request<hyper::Body>| RpcMeta::default()),
+        };
+        let cors_domains = Some(allowed_origins);
+        let max_body_bytes = self.config.max_request_body_bytes;
+
+        // 接続はこのランタイムのワーカースレッド（threads）で並行して処理する
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(self.config.threads.max(1))
+            .thread_name("rpc-http")
+            .enable_all()
+            .build()?;
+
+        let addr = listener.local_addr()?;
+        listener.set_nonblocking(true)?;
+        let listener = {
+            let _guard = runtime.enter();
+            tokio::net::TcpListener::from_std(listener)?
+        };
 
-        // notes_update: メモを更新 - オブジェクトパラメータに対応
-        let app_handle_clone = app_handle.clone();
-        let logger_clone = self.logger.clone();
-        io.add_method("notes_update", move |params: Params| {
-            let logger = logger_clone.clone();
-            let app_handle = app_handle_clone.clone();
-            
-            async move {
-                // パラメータの文字列表現を取得してログに追加
-                if let Ok(params_str) = serde_json::to_string(&params) {
-                    let mut logs = logger.logs.lock().unwrap();
-                    if let Some(last_entry) = logs.back_mut() {
-                        last_entry.body = Some(params_str);
+        let accept_task = runtime.spawn(async move {
+            loop {
+                let (stream, peer) = match listener.accept().await {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        eprintln!("Failed to accept connection: {}", e);
+                        tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                        continue;
                     }
-                }
-                
-                // パラメータからID、タイトル、内容を取得
-                let (id, title, content) = match params {
-                    // 新しいオブジェクト形式
-                    Params::Map(map) => {
-                        let id = match map.get("id") {
-                            Some(Value::String(s)) => s.clone(),
-                            _ => return Err(Error::invalid_params("Missing or invalid 'id' parameter")),
-                        };
-                        
-                        let title = match map.get("title") {
-                            Some(Value::String(s)) => Some(s.clone()),
-                            Some(Value::Null) => None,
-                            None => None,
-                            _ => return Err(Error::invalid_params("Invalid 'title' parameter")),
-                        };
-                        
-                        let content = match map.get("content") {
-                            Some(Value::String(s)) => Some(s.clone()),
-                            Some(Value::Null) => None,
-                            None => None,
-                            _ => return Err(Error::invalid_params("Invalid 'content' parameter")),
-                        };
-                        
-                        (id, title, content)
-                    },
-                    // 後方互換性のための配列形式サポート
-                    Params::Array(array) => {
-                        if array.len() < 3 {
-                            return Err(Error::invalid_params("Expected id, title and content parameters"));
-                        }
-                        
-                        let id = match array.get(0) {
-                            Some(Value::String(s)) => s.clone(),
-                            _ => return Err(Error::invalid_params("Expected string parameter for note ID")),
-                        };
-                        
-                        let title = match array.get(1) {
-                            Some(Value::String(s)) => Some(s.clone()),
-                            Some(Value::Null) => None,
-                            _ => return Err(Error::invalid_params("Expected string or null for title")),
-                        };
-                        
-                        let content = match array.get(2) {
-                            Some(Value::String(s)) => Some(s.clone()),
-                            Some(Value::Null) => None,
-                            _ => return Err(Error::invalid_params("Expected string or null for content")),
-                        };
-                        
-                        (id, title, content)
-                    },
-                    _ => return Err(Error::invalid_params("Expected object or array params")),
-                };
-                
-                // NoteStoreを取得
-                let note_store = match app_handle.try_state::<crate::NotesState>() {
-                    Some(state) => state.0.clone(),
-                    None => return Err(Error::internal_error()),
                 };
-                
-                // メモを更新
-                match note_store.update(&id, title, content) {
-                    Ok(Some(note)) => match serde_json::to_value(note) {
-                        Ok(value) => Ok(value),
-                        Err(_) => Err(Error::internal_error()),
-                    },
-                    Ok(None) => Ok(Value::Null),
-                    Err(_) => Err(Error::internal_error()),
-                }
+
+                let handler = HttpHandler::new(
+                    rpc.downgrade(),
+                    cors_domains.clone(),
+                    None,
+                    AccessControlAllowHeaders::Any,
+                    None,
+                    middleware.clone(),
+                    RestApi::Disabled,
+                    None,
+                    max_body_bytes,
+                    true,
+                );
+                // リスナーを閉じた後も、受け付けた接続のリクエストは最後まで処理する
+                let rpc = rpc.clone();
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    // ハンドシェイクの失敗（証明書を信頼しないクライアントなど）や切断は接続ごとに閉じるだけ
+                    let _ = serve_connection(stream, peer, acceptor, handler).await;
+                    drop(rpc);
+                });
             }
         });
 
-        // notes_delete: メモを削除 - オブジェクトパラメータに対応
-        let app_handle_clone = app_handle.clone();
-        let logger_clone = self.logger.clone();
-        io.add_method("notes_delete", move |params: Params| {
-            let logger = logger_clone.clone();
-            let app_handle = app_handle_clone.clone();
-            
-            async move {
-                // パラメータの文字列表現を取得してログに追加
-                if let Ok(params_str) = serde_json::to_string(&params) {
-                    let mut logs = logger.logs.lock().unwrap();
-                    if let Some(last_entry) = logs.back_mut() {
-                        last_entry.body = Some(params_str);
-                    }
+        Ok(HttpListener {
+            addr,
+            fingerprint,
+            accept_task,
+            runtime: Some(runtime),
+        })
+    }
+
+    // WebSocketリスナーを起動（HTTPと同様に候補ポートを順に試す）
+    fn start_ws(&self, io: RpcHandler) -> Result<ws_server::Server, Box<dyn std::error::Error>> {
+        let allowed_origins: Vec<ws_server::Origin> = self
+            .config
+            .allowed_origins
+            .iter()
+            .map(|origin| origin.as_str().into())
+            .collect();
+
+        let mut last_error: Option<ws_server::Error> = None;
+
+        for port in self.config.ws_candidate_ports() {
+            let addr = self.config.socket_addr_with_port(port)?;
+
+            let pending = Arc::new(Mutex::new(None));
+            let middleware = WsAuthMiddleware {
+                logger: self.logger.clone(),
+                auth: self.auth.clone(),
+                pending: pending.clone(),
+                drain: self.drain.clone(),
+            };
+
+            // ミドルウェアで認証した結果と接続ごとのセッションをメタデータにする
+            let extractor = move |context: &ws_server::RequestContext| {
+                let (auth, resource) = pending.lock().unwrap().take().unwrap_or_default();
+                RpcMeta {
+                    auth,
+                    session: Some(Arc::new(Session::new(context.sender()))),
+                    transport: Transport::WebSocket,
+                    endpoint: Some(resource),
+                    request_id: None,
+                    batch: None,
+                    replay_of: None,
                 }
-                
-                // パラメータからIDを取得
-                let id = match params {
-                    // 新しいオブジェクト形式
-                    Params::Map(map) => {
-                        match map.get("id") {
-                            Some(Value::String(s)) => s.clone(),
-                            _ => return Err(Error::invalid_params("Missing or invalid 'id' parameter")),
-                        }
-                    },
-                    // 後方互換性のための配列形式サポート
-                    Params::Array(array) => {
-                        match array.get(0) {
-                            Some(Value::String(s)) => s.clone(),
-                            _ => return Err(Error::invalid_params("Expected string parameter for note ID")),
-                        }
-                    },
-                    _ => return Err(Error::invalid_params("Expected object or array params")),
-                };
-                
-                // NoteStoreを取得
-                let note_store = match app_handle.try_state::<crate::NotesState>() {
-                    Some(state) => state.0.clone(),
-                    None => return Err(Error::internal_error()),
-                };
-                
-                // メモを削除
-                match note_store.delete(&id) {
-                    Ok(true) => Ok(Value::Bool(true)),
-                    Ok(false) => Ok(Value::Bool(false)),
-                    Err(_) => Err(Error::internal_error()),
+            };
+
+            let result = ws_server::ServerBuilder::with_meta_extractor(io.clone(), extractor)
+                .allowed_origins(ws_server::DomainsValidation::AllowOnly(allowed_origins.clone()))
+                .request_middleware(middleware)
+                .start(&addr);
+
+            match result {
+                Ok(server) => return Ok(server),
+                Err(e) if is_ws_addr_in_use(&e) => {
+                    eprintln!("WebSocket port {} is already in use, trying next candidate", port);
+                    last_error = Some(e);
                 }
+                Err(e) => return Err(format!("Failed to start WebSocket server: {}", e).into()),
             }
-        });
+        }
 
-        // ミドルウェアの準備
-        let logger_clone = self.logger.clone();
-        let middleware = LoggingMiddleware { 
-            logger: logger_clone 
+        let reason = last_error.map(|e| e.to_string()).unwrap_or_default();
+        Err(AppError::Unavailable(format!("Failed to start WebSocket server: no available port ({})", reason)).into())
+    }
+
+    // IPCソケットを起動（Unixではドメインソケット、Windowsでは名前付きパイプ）
+    fn start_ipc(&self, io: RpcHandler, path: &str) -> Result<ipc_server::Server, Box<dyn std::error::Error>> {
+        check_ipc_path(path).map_err(|e| AppError::validation(Some("ipc_path"), e))?;
+
+        // ソケットは現在のユーザーのみ接続可能（0600）なので、トークン認証は行わない
+        let endpoint = path.to_string();
+        let extractor = move |context: &ipc_server::RequestContext| RpcMeta {
+            auth: AuthContext::full_access(None),
+            session: Some(Arc::new(Session::new(context.sender.clone()))),
+            transport: Transport::Ipc,
+            endpoint: Some(endpoint.clone()),
+            request_id: None,
+            batch: None,
+            replay_of: None,
         };
 
-        // Build server with middleware
-        let server = ServerBuilder::new(io)
-            .threads(1)
-            .cors(jsonrpc_http_server::DomainsValidation::AllowOnly(vec![
-                "http://localhost:1420".into(), // Development UI
-                "tauri://localhost".into(),     // Tauri app
-                "http://localhost:3000".into(), // For external testing
-            ]))
-            .request_middleware(middleware)
-            .start_http(&"127.0.0.1:3030".parse()?)
-            .map_err(|e| format!("Failed to start JSON-RPC server: {}", e))?;
-
-        self.server = Some(server);
-        self.running = true;
-        
+        ipc_server::ServerBuilder::with_meta_extractor(io, extractor)
+            .set_security_attributes(ipc_server::SecurityAttributes::empty())
+            .start(path)
+            .map_err(|e| format!("Failed to start IPC server at {}: {}", path, e).into())
+    }
+
+    // 実際のURLをエンドポイントファイルに書き出す
+    fn write_endpoint_file(&self, payload: &ServerStartedPayload) -> Result<(), Box<dyn std::error::Error>> {
+        let app_data_dir = self.app_data_dir()?;
+        std::fs::write(app_data_dir.join(ENDPOINT_FILE_NAME), serde_json::to_string_pretty(payload)?)?;
         Ok(())
     }
 
+    // 最後に起動したポートを起動時の設定に保存
+    fn save_last_port(&self, port: Option<u16>) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(app_data_dir) = &self.app_data_dir {
+            let mut launch = LaunchSettings::load(app_data_dir)?;
+            if launch.last_port != port {
+                launch.last_port = port;
+                launch.save(app_data_dir)?;
+            }
+        }
+        Ok(())
+    }
+
+    // 停止時にエンドポイントファイルを削除
+    fn remove_endpoint_file(&self) {
+        if let Some(app_data_dir) = &self.app_data_dir {
+            let _ = std::fs::remove_file(app_data_dir.join(ENDPOINT_FILE_NAME));
+        }
+    }
+
+    // 新しいリクエストの受け付けを止め、処理中のリクエストの完了を shutdown_timeout_secs まで待ってから停止する
+    // 時間内に終わらなかったリクエストは接続ごと閉じる
     pub fn stop(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let pending = self.begin_stop()?;
+        let unfinished_requests = pending.wait();
+        self.finish_stop(unfinished_requests);
+        Ok(())
+    }
+
+    // 新しいリクエストの受け付けを止める
+    // 続けて PendingStop::wait で処理中のリクエストを待ち、finish_stop で停止する（待つ間はロックを保持しなくてよい）
+    pub fn begin_stop(&mut self) -> Result<PendingStop, Box<dyn std::error::Error>> {
         if !self.running {
-            return Err("Server is not running".into());
+            return Err(AppError::Unavailable("Server is not running".to_string()).into());
+        }
+        if self.stopping {
+            return Err(AppError::Unavailable("Server is already stopping".to_string()).into());
         }
+        self.stopping = true;
+
+        let timeout_secs = self.config.shutdown_timeout_secs;
+        self.emit(
+            EVENT_SERVER_STOPPING,
+            ServerStoppingPayload {
+                timeout_secs,
+                in_flight: self.drain.active(),
+            },
+        );
+
+        // HTTP・HTTPSはリスナーを閉じて新しい接続を拒否する（接続済みの接続とWebSocketには503で応答する）
+        self.drain.close();
+        if let Some(server) = &self.server {
+            server.stop_accepting();
+        }
+
+        Ok(PendingStop {
+            drain: self.drain.clone(),
+            timeout: Duration::from_secs(timeout_secs),
+        })
+    }
+
+    // 処理中のリクエストを待った後にすべてのリスナーを閉じる
+    pub fn finish_stop(&mut self, unfinished_requests: usize) {
+        if !self.stopping {
+            return;
+        }
+        if unfinished_requests > 0 {
+            eprintln!("Forcing server shutdown with {} request(s) still in flight", unfinished_requests);
+        }
+
+        if let Some(ws_server) = self.ws_server.take() {
+            ws_server.close();
+        }
+        self.ws_bound_addr = None;
+
+        // 購読者への転送を停止
+        if let Some(listener_id) = self.note_listener_id.take() {
+            if let Some(note_store) = &self.note_store {
+                note_store.remove_change_listener(listener_id);
+            }
+        }
+        self.subscriptions.clear();
+
+        if let Some(ipc_server) = self.ipc_server.take() {
+            ipc_server.close();
+        }
+        self.ipc_path = None;
 
         if let Some(server) = self.server.take() {
             server.close();
-            self.running = false;
-            Ok(())
-        } else {
-            Err("Server instance not found".into())
+        }
+        self.bound_addr = None;
+
+        self.io = None;
+        self.running = false;
+        self.stopping = false;
+        self.remove_endpoint_file();
+        self.logger.flush_notifications();
+
+        self.emit(
+            EVENT_SERVER_STOPPED,
+            ServerStoppedPayload {
+                forced: unfinished_requests > 0,
+                unfinished_requests,
+            },
+        );
+    }
+
+    // フロントエンドにイベントを通知（失敗しても処理は続ける）
+    fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        if let Some(app_handle) = &self.app_handle {
+            if let Err(e) = app_handle.emit(event, payload) {
+                eprintln!("Failed to emit {} event: {}", event, e);
+            }
         }
     }
 
@@ -570,6 +1358,18 @@ impl JsonRpcServer {
         self.logger.get_logs()
     }
 
+    // ログの検索・書き出しに使う（ファイルの読み込み中にサーバーのロックを保持しないよう取り出す）
+    pub(crate) fn logger(&self) -> Arc<Logger> {
+        self.logger.clone()
+    }
+
+    // 記録されたリクエストの再実行に使う起動中のRPCハンドラー
+    pub(crate) fn rpc_handler(&self) -> Result<RpcHandler, AppError> {
+        self.io
+            .clone()
+            .ok_or_else(|| AppError::Unavailable("Server is not running".to_string()))
+    }
+
     pub fn clear_logs(&self) {
         // Arcの中のLoggerのメソッドを呼び出す（内部でMutexを使用）
         self.logger.clear_logs();
@@ -585,4 +1385,54 @@ mod tests {
         let server = JsonRpcServer::new();
         assert!(!server.is_running());
     }
+
+    #[test]
+    fn test_query_access_token() {
+        assert_eq!(query_access_token("/?access_token=abc"), Some("abc"));
+        assert_eq!(query_access_token("/?foo=1&access_token=abc"), Some("abc"));
+        assert_eq!(query_access_token("/?access_token="), None);
+        assert_eq!(query_access_token("/"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ipc_path_is_checked() {
+        let dir = std::env::temp_dir().join(format!("ipc-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+
+        assert!(check_ipc_path(&path("missing.sock")).is_ok());
+
+        // 通常のファイルは削除させない
+        std::fs::write(dir.join("notes.txt"), "keep").unwrap();
+        assert!(check_ipc_path(&path("notes.txt")).is_err());
+
+        // 待ち受け中のソケットは使わず、残っているだけのソケットは使う
+        let listener = std::os::unix::net::UnixListener::bind(dir.join("live.sock")).unwrap();
+        assert!(check_ipc_path(&path("live.sock")).is_err());
+        drop(listener);
+        assert!(check_ipc_path(&path("live.sock")).is_ok());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_preferred_port_is_tried_first() {
+        let mut server = JsonRpcServer::new();
+        server.set_config(ServerConfig {
+            port: 4000,
+            port_range_end: Some(4002),
+            ws_enabled: true,
+            ..ServerConfig::default()
+        });
+        assert_eq!(server.http_candidate_ports()[0], 4000);
+
+        server.set_preferred_port(Some(4001));
+        let ports = server.http_candidate_ports();
+        assert_eq!(ports[..3], [4001, 4000, 4002]);
+
+        // WebSocketのポートとは重複させない
+        server.set_preferred_port(Some(server.config().ws_port));
+        assert_eq!(server.http_candidate_ports()[0], 4000);
+    }
 }
\ No newline at end of file
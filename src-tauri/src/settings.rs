@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::config::ServerConfig;
+use crate::error::AppError;
+
+// 起動時の設定ファイル名（アプリのデータディレクトリに保存）
+const LAUNCH_SETTINGS_FILE_NAME: &str = "server-settings.json";
+
+// アプリの起動時に適用する設定
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct LaunchSettings {
+    // アプリの起動時にサーバーを起動する
+    pub auto_start: bool,
+    // 最後に起動したHTTPリスナーのポート（次回の起動時に候補ポートより先に試す）
+    pub last_port: Option<u16>,
+}
+
+impl LaunchSettings {
+    // ファイルが存在しない場合はデフォルト値を使用
+    pub fn load(app_data_dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let file_path = app_data_dir.join(LAUNCH_SETTINGS_FILE_NAME);
+        if !file_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let file_content = fs::read_to_string(&file_path)?;
+        Ok(serde_json::from_str(&file_content)?)
+    }
+
+    pub fn save(&self, app_data_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if !app_data_dir.exists() {
+            fs::create_dir_all(app_data_dir)?;
+        }
+
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(app_data_dir.join(LAUNCH_SETTINGS_FILE_NAME), json)?;
+        Ok(())
+    }
+
+    // 次回の起動時に最初に試すポート（環境変数でポートが指定されている場合は使わない）
+    pub fn preferred_port(&self) -> Option<u16> {
+        self.last_port.filter(|_| !ServerConfig::port_overridden_by_env())
+    }
+}
+
+// get_server_settings / set_server_settings で読み書きする設定
+// 保存先は項目ごとに異なる（起動時の設定は server-settings.json、認証は auth-token.json、ログは server-config.json）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ServerSettings {
+    pub auto_start: bool,
+    pub last_port: Option<u16>,
+    pub auth_enabled: bool,
+    // ログの保持（ServerConfig の同名の項目）
+    pub log_persist: bool,
+    pub log_memory_entries: usize,
+    pub log_retention_files: usize,
+    pub log_max_file_age_secs: Option<u64>,
+}
+
+impl ServerSettings {
+    pub fn new(launch: &LaunchSettings, config: &ServerConfig, auth_enabled: bool) -> Self {
+        Self {
+            auto_start: launch.auto_start,
+            last_port: launch.last_port,
+            auth_enabled,
+            log_persist: config.log_persist,
+            log_memory_entries: config.log_memory_entries,
+            log_retention_files: config.log_retention_files,
+            log_max_file_age_secs: config.log_max_file_age_secs,
+        }
+    }
+
+    pub fn launch_settings(&self) -> LaunchSettings {
+        LaunchSettings {
+            auto_start: self.auto_start,
+            last_port: self.last_port,
+        }
+    }
+
+    // ログの保持の設定を反映したサーバー設定
+    pub fn apply_to(&self, config: &ServerConfig) -> ServerConfig {
+        ServerConfig {
+            log_persist: self.log_persist,
+            log_memory_entries: self.log_memory_entries,
+            log_retention_files: self.log_retention_files,
+            log_max_file_age_secs: self.log_max_file_age_secs,
+            ..config.clone()
+        }
+    }
+
+    // 現在のサーバー設定と組み合わせて検証
+    pub fn validate(&self, config: &ServerConfig) -> Result<(), AppError> {
+        if self.last_port == Some(0) {
+            return Err(AppError::validation(
+                Some("last_port"),
+                "last_port must be between 1 and 65535 (null to use the configured port)",
+            ));
+        }
+        if self.log_memory_entries == 0 {
+            return Err(AppError::validation(Some("log_memory_entries"), "log_memory_entries must be at least 1"));
+        }
+        if self.log_max_file_age_secs == Some(0) {
+            return Err(AppError::validation(
+                Some("log_max_file_age_secs"),
+                "log_max_file_age_secs must be at least 1 (null to disable age-based rotation)",
+            ));
+        }
+
+        if let Some(port) = self.last_port {
+            if config.ws_enabled && port == config.ws_port {
+                return Err(AppError::validation(Some("last_port"), format!("last_port ({}) must differ from ws_port", port)));
+            }
+        }
+
+        self.apply_to(config)
+            .validate()
+            .map_err(|e| AppError::validation(None, e))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> ServerSettings {
+        ServerSettings::new(&LaunchSettings::default(), &ServerConfig::default(), false)
+    }
+
+    #[test]
+    fn test_settings_are_validated() {
+        let config = ServerConfig {
+            ws_enabled: true,
+            ..ServerConfig::default()
+        };
+        assert!(settings().validate(&config).is_ok());
+
+        let invalid = ServerSettings {
+            log_memory_entries: 0,
+            ..settings()
+        };
+        assert_eq!(invalid.validate(&config).unwrap_err().data()["field"], "log_memory_entries");
+
+        // WebSocketと同じポートは使えない
+        let invalid = ServerSettings {
+            last_port: Some(config.ws_port),
+            ..settings()
+        };
+        assert_eq!(invalid.validate(&config).unwrap_err().data()["field"], "last_port");
+    }
+
+    #[test]
+    fn test_launch_settings_round_trip() {
+        let dir = std::env::temp_dir().join(format!("settings-test-{}", uuid::Uuid::new_v4()));
+        assert_eq!(LaunchSettings::load(&dir).unwrap(), LaunchSettings::default());
+
+        let launch = LaunchSettings {
+            auto_start: true,
+            last_port: Some(4040),
+        };
+        launch.save(&dir).unwrap();
+        assert_eq!(LaunchSettings::load(&dir).unwrap(), launch);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
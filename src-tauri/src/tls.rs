@@ -0,0 +1,170 @@
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio_rustls::TlsAcceptor;
+
+use crate::config::ServerConfig;
+use crate::error::AppError;
+use crate::secret_file::write_secret_file;
+
+// 自己署名証明書と秘密鍵の保存先（アプリのデータディレクトリ）
+const CERT_FILE_NAME: &str = "tls-cert.pem";
+const KEY_FILE_NAME: &str = "tls-key.pem";
+// 自己署名証明書に含めたホスト名（host を変更した場合に作り直すため証明書と一緒に保存する）
+const NAMES_FILE_NAME: &str = "tls-cert-names.json";
+
+// 自己署名証明書に常に含めるホスト名
+const DEFAULT_SUBJECT_ALT_NAMES: &[&str] = &["localhost", "127.0.0.1", "::1"];
+
+// HTTPSリスナーで使う証明書チェーンと秘密鍵
+pub struct TlsIdentity {
+    certs: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+}
+
+impl TlsIdentity {
+    // 設定で指定されたPEMファイルを読み込む
+    // 指定がない場合はデータディレクトリの自己署名証明書を使い、存在しなければ生成する
+    pub fn load_or_generate(config: &ServerConfig, app_data_dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        // 設定で指定したファイルの誤りは検証エラー（項目名付き）、自己署名証明書の誤りは保存先のエラー
+        let user_supplied = config.tls_cert_path.is_some() && config.tls_key_path.is_some();
+        let invalid = |field: &str, message: String| -> Box<dyn std::error::Error> {
+            if user_supplied {
+                AppError::validation(Some(field), message).into()
+            } else {
+                AppError::storage(message).into()
+            }
+        };
+
+        let (cert_path, key_path) = match (&config.tls_cert_path, &config.tls_key_path) {
+            (Some(cert_path), Some(key_path)) => (PathBuf::from(cert_path), PathBuf::from(key_path)),
+            _ => {
+                let cert_path = app_data_dir.join(CERT_FILE_NAME);
+                let key_path = app_data_dir.join(KEY_FILE_NAME);
+                let names_path = app_data_dir.join(NAMES_FILE_NAME);
+                let names = subject_alt_names(&config.host);
+                if !cert_path.exists() || !key_path.exists() || stored_names(&names_path) != Some(names.clone()) {
+                    generate_self_signed(names, &cert_path, &key_path, &names_path).map_err(AppError::storage)?;
+                }
+                (cert_path, key_path)
+            }
+        };
+
+        let certs = CertificateDer::pem_file_iter(&cert_path)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| invalid("tls_cert_path", format!("Failed to read TLS certificate {}: {}", cert_path.display(), e)))?;
+        if certs.is_empty() {
+            return Err(invalid("tls_cert_path", format!("No certificate found in {}", cert_path.display())));
+        }
+        let key = PrivateKeyDer::from_pem_file(&key_path)
+            .map_err(|e| invalid("tls_key_path", format!("Failed to read TLS private key {}: {}", key_path.display(), e)))?;
+
+        // 証明書と秘密鍵の組み合わせの誤りもポートを開く前に検出する
+        let identity = Self { certs, key };
+        identity
+            .server_config()
+            .map_err(|e| invalid("tls_key_path", format!("Invalid TLS certificate or private key: {}", e)))?;
+        Ok(identity)
+    }
+
+    // 証明書（チェーンの先頭）のSHA-256フィンガープリント（"AB:CD:..." 形式）
+    pub fn fingerprint(&self) -> String {
+        Sha256::digest(&self.certs[0])
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+
+    // HTTPSリスナーで接続ごとにTLSのハンドシェイクを行う
+    pub fn acceptor(&self) -> Result<TlsAcceptor, rustls::Error> {
+        Ok(TlsAcceptor::from(Arc::new(self.server_config()?)))
+    }
+
+    fn server_config(&self) -> Result<rustls::ServerConfig, rustls::Error> {
+        // 依存クレートの機能によって既定のプロバイダーが決まらないよう、明示的に指定する
+        rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_single_cert(self.certs.clone(), self.key.clone_key())
+    }
+}
+
+// 自己署名証明書に含めるホスト名
+fn subject_alt_names(host: &str) -> Vec<String> {
+    let mut names: Vec<String> = DEFAULT_SUBJECT_ALT_NAMES.iter().map(|name| name.to_string()).collect();
+    // すべてのアドレスで待ち受ける場合（0.0.0.0, ::）はホスト名として使えない
+    let unspecified = host.parse::<IpAddr>().is_ok_and(|ip| ip.is_unspecified());
+    if !unspecified && !names.iter().any(|name| name == host) {
+        names.push(host.to_string());
+    }
+    names
+}
+
+// 保存されている自己署名証明書のホスト名（以前のバージョンで生成した証明書ではNone）
+fn stored_names(names_path: &Path) -> Option<Vec<String>> {
+    serde_json::from_str(&fs::read_to_string(names_path).ok()?).ok()
+}
+
+// 自己署名証明書と秘密鍵を生成して保存（秘密鍵は現在のユーザーのみ読み書き可能なファイルに書き込む）
+// ホスト名は最後に書き込むため、途中で失敗した場合は次回の起動時に作り直す
+fn generate_self_signed(names: Vec<String>, cert_path: &Path, key_path: &Path, names_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let certified = rcgen::generate_simple_self_signed(names.clone())?;
+    if let Some(dir) = cert_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    write_secret_file(key_path, certified.key_pair.serialize_pem().as_bytes())?;
+    fs::write(cert_path, certified.cert.pem())?;
+    fs::write(names_path, serde_json::to_string(&names)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_signed_certificate_is_reused() {
+        let dir = std::env::temp_dir().join(format!("tls-test-{}", uuid::Uuid::new_v4()));
+        let config = ServerConfig::default();
+
+        let identity = TlsIdentity::load_or_generate(&config, &dir).unwrap();
+        let fingerprint = identity.fingerprint();
+        assert_eq!(fingerprint.len(), 32 * 3 - 1);
+        assert!(identity.server_config().is_ok());
+
+        // 2回目以降は保存した証明書を読み込む
+        let identity = TlsIdentity::load_or_generate(&config, &dir).unwrap();
+        assert_eq!(identity.fingerprint(), fingerprint);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(dir.join(KEY_FILE_NAME)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // host を変更した場合は作り直す
+        let config = ServerConfig {
+            host: "192.0.2.10".to_string(),
+            ..config
+        };
+        let identity = TlsIdentity::load_or_generate(&config, &dir).unwrap();
+        assert_ne!(identity.fingerprint(), fingerprint);
+        assert!(stored_names(&dir.join(NAMES_FILE_NAME)).unwrap().contains(&config.host));
+
+        // 指定したPEMファイルが存在しない場合は生成せずにエラーにする
+        let config = ServerConfig {
+            tls_cert_path: Some(dir.join("missing-cert.pem").to_string_lossy().into_owned()),
+            tls_key_path: Some(dir.join("missing-key.pem").to_string_lossy().into_owned()),
+            ..config
+        };
+        assert!(TlsIdentity::load_or_generate(&config, &dir).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
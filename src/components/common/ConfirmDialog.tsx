@@ -5,6 +5,8 @@ export interface ConfirmDialogProps {
   isOpen: boolean;
   title: string;
   message: string;
+  // 確認ボタンのラベル（デフォルトは「削除」）
+  confirmLabel?: string;
   onConfirm: () => void;
   onCancel: () => void;
 }
@@ -14,6 +16,7 @@ const ConfirmDialog: React.FC<ConfirmDialogProps> = ({
   isOpen, 
   title, 
   message, 
+  confirmLabel = "削除",
   onConfirm, 
   onCancel 
 }) => {
@@ -40,7 +43,7 @@ const ConfirmDialog: React.FC<ConfirmDialogProps> = ({
             className="px-4 py-2 bg-red-500 text-white rounded hover:bg-red-600"
             onClick={onConfirm}
           >
-            削除
+            {confirmLabel}
           </button>
         </div>
       </div>
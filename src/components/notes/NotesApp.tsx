@@ -12,6 +12,7 @@ const NotesApp: React.FC = () => {
     noteTitle,
     noteContent,
     editMode,
+    noteError,
     setNoteTitle,
     setNoteContent,
     selectNote,
@@ -81,6 +82,11 @@ const NotesApp: React.FC = () => {
 
       {/* メモの詳細表示・編集エリア */}
       <div className="flex-grow">
+        {noteError && (
+          <div className="m-4 p-4 bg-red-50 text-red-700 rounded border-l-4 border-red-700">
+            {noteError}
+          </div>
+        )}
         {editMode ? (
           <NoteEditor
             title={noteTitle}
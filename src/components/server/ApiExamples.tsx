@@ -4,22 +4,27 @@ const ApiExamples: React.FC = () => {
   // Sample requests for demo
   const notesListExample = `curl -X POST http://127.0.0.1:3030 \\
   -H "Content-Type: application/json" \\
+  -H "Authorization: Bearer YOUR_TOKEN_HERE" \\
   -d '{"jsonrpc": "2.0", "method": "notes_list", "params": {}, "id": 1}'`;
 
   const notesGetExample = `curl -X POST http://127.0.0.1:3030 \\
   -H "Content-Type: application/json" \\
+  -H "Authorization: Bearer YOUR_TOKEN_HERE" \\
   -d '{"jsonrpc": "2.0", "method": "notes_get", "params": {"id": "NOTE_ID_HERE"}, "id": 1}'`;
 
   const notesCreateExample = `curl -X POST http://127.0.0.1:3030 \\
   -H "Content-Type: application/json" \\
+  -H "Authorization: Bearer YOUR_TOKEN_HERE" \\
   -d '{"jsonrpc": "2.0", "method": "notes_create", "params": {"title": "New Note Title", "content": "Note content goes here"}, "id": 1}'`;
 
   const notesUpdateExample = `curl -X POST http://127.0.0.1:3030 \\
   -H "Content-Type: application/json" \\
+  -H "Authorization: Bearer YOUR_TOKEN_HERE" \\
   -d '{"jsonrpc": "2.0", "method": "notes_update", "params": {"id": "NOTE_ID_HERE", "title": "Updated Title", "content": "Updated content"}, "id": 1}'`;
 
   const notesDeleteExample = `curl -X POST http://127.0.0.1:3030 \\
   -H "Content-Type: application/json" \\
+  -H "Authorization: Bearer YOUR_TOKEN_HERE" \\
   -d '{"jsonrpc": "2.0", "method": "notes_delete", "params": {"id": "NOTE_ID_HERE"}, "id": 1}'`;
 
   return (
@@ -86,6 +91,12 @@ const ApiExamples: React.FC = () => {
         <li>
           <span className="font-semibold text-slate-800">system_info</span> - システム情報を返す
         </li>
+        <li>
+          <span className="font-semibold text-slate-800">server_logs_query</span> - アクセスログを条件を指定して検索
+        </li>
+        <li>
+          <span className="font-semibold text-slate-800">rpc.discover</span> - OpenRPC 形式の API ドキュメントを返す（<code>GET /openrpc.json</code> でも取得可能）
+        </li>
       </ul>
     </div>
   );
@@ -1,5 +1,5 @@
 import React from 'react';
-import { LogEntry } from '../../types/server';
+import { LogEntry, ReplayResult } from '../../types/server';
 
 interface LogViewerProps {
   logs: LogEntry[];
@@ -9,6 +9,10 @@ interface LogViewerProps {
   setAutoRefreshLogs: (auto: boolean) => void;
   onRefresh: () => void;
   onClear: () => void;
+  onReplay: (log: LogEntry) => void;
+  replayResult: ReplayResult | null;
+  replayError: string;
+  onCloseReplay: () => void;
   formatTimestamp: (timestamp: number) => string;
   formatJSON: (json: string | null) => string;
 }
@@ -21,6 +25,10 @@ const LogViewer: React.FC<LogViewerProps> = ({
   setAutoRefreshLogs,
   onRefresh,
   onClear,
+  onReplay,
+  replayResult,
+  replayError,
+  onCloseReplay,
   formatTimestamp,
   formatJSON
 }) => {
@@ -66,36 +74,132 @@ const LogViewer: React.FC<LogViewerProps> = ({
         </div>
       </div>
 
+      {showLogs && (replayResult || replayError) && (
+        <div className="border rounded-md p-3 bg-white mb-4">
+          <div className="flex justify-between items-center mb-2">
+            <div className="font-medium text-sm text-gray-700">
+              再実行の結果
+              {replayResult && (
+                <span className="ml-2 font-mono text-xs text-gray-500">{replayResult.replay_of}</span>
+              )}
+            </div>
+            <button onClick={onCloseReplay} className="text-xs text-gray-500 hover:text-gray-700">
+              閉じる
+            </button>
+          </div>
+          {replayError && <div className="text-sm text-red-500">{replayError}</div>}
+          {replayResult && (
+            <>
+              <div className="text-sm mb-2">
+                {replayResult.matches === null ? (
+                  <span className="text-gray-500">元のレスポンスが記録されていないため比較できません</span>
+                ) : replayResult.matches ? (
+                  <span className="text-green-600">元のレスポンスと一致しました</span>
+                ) : (
+                  <span className="text-amber-600">
+                    元のレスポンスと異なります: {replayResult.differences.join(", ")}
+                  </span>
+                )}
+              </div>
+              <pre className="bg-gray-100 p-2 rounded text-xs overflow-x-auto">
+                {JSON.stringify(replayResult.response, null, 2) ?? "No data"}
+              </pre>
+            </>
+          )}
+        </div>
+      )}
+
       {showLogs && (
         <div className="max-h-96 overflow-y-auto">
           {logs.length === 0 ? (
             <p className="text-gray-500 text-center py-4">ログがありません</p>
           ) : (
             <div className="space-y-4">
-              {logs.slice().reverse().map((log, index) => (
-                <div key={index} className="border rounded-md p-3 bg-white">
+              {logs.slice().reverse().map((log) => (
+                <div key={log.request_id} className="border rounded-md p-3 bg-white">
                   <div className="text-xs text-gray-500 mb-2">
                     {formatTimestamp(log.timestamp)}
+                    <span className="ml-2 font-mono">{log.request_id}</span>
+                    {log.replay_of && (
+                      <span className="ml-2">（{log.replay_of} の再実行）</span>
+                    )}
+                    {log.body && !log.body_truncated && (
+                      <button
+                        onClick={() => onReplay(log)}
+                        className="ml-2 text-blue-500 hover:text-blue-700"
+                      >
+                        再実行
+                      </button>
+                    )}
                   </div>
                   <div className="mb-2">
                     <div className="font-medium text-sm text-gray-700 mb-1">
                       {log.method} {log.uri}
+                      {log.rpc_method && (
+                        <span className="ml-2 text-xs text-blue-600">{log.rpc_method}</span>
+                      )}
+                      {log.status !== null && (
+                        <span className={`ml-2 text-xs ${log.status >= 400 ? "text-red-500" : "text-green-600"}`}>
+                          {log.status}
+                        </span>
+                      )}
+                      {log.elapsed_ms !== null && (
+                        <span className="ml-2 text-xs text-gray-500">{log.elapsed_ms} ms</span>
+                      )}
                     </div>
                     <div className="text-xs text-gray-500 mb-2">
-                      ヘッダー: {log.headers}
+                      ヘッダー: {log.headers.map((header) => `${header.name}: ${header.value}`).join(", ")}
                     </div>
                   </div>
                   {log.body && (
                     <div className="mb-2">
-                      <div className="font-medium text-sm text-gray-700 mb-1">リクエスト:</div>
+                      <div className="font-medium text-sm text-gray-700 mb-1">
+                        リクエスト:
+                        {log.body_truncated && (
+                          <span className="ml-2 text-xs text-amber-600">
+                            （{log.body_size} バイト中、先頭のみ表示）
+                          </span>
+                        )}
+                      </div>
                       <pre className="bg-gray-100 p-2 rounded text-xs overflow-x-auto">
                         {formatJSON(log.body)}
                       </pre>
                     </div>
                   )}
+                  {log.items.length > 0 && (
+                    <div className="mb-2">
+                      <div className="font-medium text-sm text-gray-700 mb-1">
+                        バッチ（{log.items.length} 件）:
+                      </div>
+                      <div className="space-y-1">
+                        {log.items.map((item) => (
+                          <div key={item.index} className="bg-gray-100 p-2 rounded text-xs font-mono">
+                            <span className="text-blue-600">{item.rpc_method ?? "(invalid)"}</span>
+                            {item.rpc_id !== null && <span className="ml-2 text-gray-500">id: {item.rpc_id}</span>}
+                            {item.elapsed_ms !== null && <span className="ml-2 text-gray-500">{item.elapsed_ms} ms</span>}
+                            {item.truncated && <span className="ml-2 text-amber-600">（一部を省略）</span>}
+                            {item.params && <div className="overflow-x-auto">params: {item.params}</div>}
+                            {item.result && <div className="overflow-x-auto">result: {item.result}</div>}
+                            {item.error && (
+                              <div className="text-red-500">
+                                error: {item.error.code} {item.error.message}
+                              </div>
+                            )}
+                          </div>
+                        ))}
+                      </div>
+                    </div>
+                  )}
                   {log.response && (
                     <div>
-                      <div className="font-medium text-sm text-gray-700 mb-1">レスポンス:</div>
+                      <div className="font-medium text-sm text-gray-700 mb-1">
+                        レスポンス:
+                        {log.response_truncated && (
+                          <span className="ml-2 text-xs text-amber-600">
+                            （{log.response_size} バイト中、先頭のみ表示）
+                          </span>
+                        )}
+                      </div>
                       <pre className="bg-gray-100 p-2 rounded text-xs overflow-x-auto">
                         {formatJSON(log.response)}
                       </pre>
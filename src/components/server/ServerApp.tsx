@@ -1,13 +1,19 @@
-import React from 'react';
+import React, { useCallback, useState } from 'react';
 import { useServer } from '../../hooks/useServer';
+import { AppError, errorMessage as toErrorMessage } from '../../types/error';
+import { LogEntry } from '../../types/server';
+import ConfirmDialog from '../common/ConfirmDialog';
 import ServerStatus from './ServerStatus';
+import ServerSettingsPanel from './ServerSettingsPanel';
 import LogViewer from './LogViewer';
 import ApiExamples from './ApiExamples';
 
 const ServerApp: React.FC = () => {
   const {
     serverRunning,
+    serverStopping,
     serverUrl,
+    tlsFingerprint,
     errorMessage,
     logs,
     showLogs,
@@ -16,12 +22,38 @@ const ServerApp: React.FC = () => {
     setAutoRefreshLogs,
     fetchLogs,
     clearLogs,
+    replayResult,
+    setReplayResult,
+    replayLog,
+    serverSettings,
+    saveServerSettings,
     startServer,
     stopServer,
+    restartServer,
     formatTimestamp,
     formatJSON
   } = useServer();
 
+  const [replayError, setReplayError] = useState("");
+  // データを変更するメソッドの再実行を確認するリクエストID
+  const [pendingReplay, setPendingReplay] = useState<LogEntry | null>(null);
+
+  // ログを再実行（確認が必要な場合はダイアログを表示）
+  const replay = useCallback(async (log: LogEntry, confirm = false) => {
+    setReplayError("");
+    try {
+      await replayLog(log.request_id, confirm);
+    } catch (error) {
+      const appError = error as AppError;
+      if (!confirm && appError.kind === 'validation' && appError.data?.field === 'confirm') {
+        setPendingReplay(log);
+        return;
+      }
+      setReplayResult(null);
+      setReplayError(toErrorMessage(error));
+    }
+  }, [replayLog, setReplayResult]);
+
   return (
     <div className="max-w-4xl mx-auto p-8 overflow-y-auto flex-grow">
       <h1 className="text-3xl font-bold text-center text-slate-800 mb-8">
@@ -30,10 +62,29 @@ const ServerApp: React.FC = () => {
 
       <ServerStatus
         isRunning={serverRunning}
+        isStopping={serverStopping}
         serverUrl={serverUrl}
+        tlsFingerprint={tlsFingerprint}
         errorMessage={errorMessage}
         onStart={startServer}
         onStop={stopServer}
+        onRestart={restartServer}
+      />
+
+      <ServerSettingsPanel settings={serverSettings} onSave={saveServerSettings} />
+
+      <ConfirmDialog
+        isOpen={pendingReplay !== null}
+        title="再実行の確認"
+        message={`${pendingReplay?.rpc_method ?? "このリクエスト"} はデータを変更します。再実行しますか？`}
+        confirmLabel="再実行"
+        onConfirm={() => {
+          if (pendingReplay) {
+            replay(pendingReplay, true);
+          }
+          setPendingReplay(null);
+        }}
+        onCancel={() => setPendingReplay(null)}
       />
 
       <LogViewer
@@ -44,6 +95,13 @@ const ServerApp: React.FC = () => {
         setAutoRefreshLogs={setAutoRefreshLogs}
         onRefresh={fetchLogs}
         onClear={clearLogs}
+        onReplay={replay}
+        replayResult={replayResult}
+        replayError={replayError}
+        onCloseReplay={() => {
+          setReplayResult(null);
+          setReplayError("");
+        }}
         formatTimestamp={formatTimestamp}
         formatJSON={formatJSON}
       />
@@ -0,0 +1,155 @@
+import React, { useEffect, useState } from 'react';
+import { AppError, errorMessage as toErrorMessage } from '../../types/error';
+import { ServerSettings } from '../../types/server';
+
+interface ServerSettingsPanelProps {
+  settings: ServerSettings | null;
+  onSave: (settings: ServerSettings) => Promise<ServerSettings>;
+}
+
+// 数値の入力欄（空欄は null）
+function parseOptionalNumber(value: string): number | null {
+  return value.trim() === "" ? null : Number(value);
+}
+
+const ServerSettingsPanel: React.FC<ServerSettingsPanelProps> = ({ settings, onSave }) => {
+  const [draft, setDraft] = useState<ServerSettings | null>(settings);
+  const [saving, setSaving] = useState(false);
+  const [message, setMessage] = useState("");
+  // 検証エラーの項目名（data.field）
+  const [errorField, setErrorField] = useState<string | null>(null);
+  const [error, setError] = useState("");
+
+  // 保存された設定（起動したポートの反映を含む）を編集中の値に反映
+  useEffect(() => {
+    setDraft(settings);
+  }, [settings]);
+
+  if (!draft) {
+    return null;
+  }
+
+  const update = (changes: Partial<ServerSettings>) => {
+    setDraft({ ...draft, ...changes });
+    setMessage("");
+  };
+
+  const save = async () => {
+    setSaving(true);
+    setError("");
+    setErrorField(null);
+    try {
+      await onSave(draft);
+      setMessage("保存しました");
+    } catch (e) {
+      const appError = e as AppError;
+      const field = appError.data?.field;
+      setErrorField(typeof field === 'string' ? field : null);
+      setError(toErrorMessage(e));
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  const inputClass = (field: string) =>
+    `w-32 border rounded px-2 py-1 text-sm ${errorField === field ? "border-red-500" : "border-gray-300"}`;
+
+  return (
+    <div className="bg-gray-50 rounded-lg p-6 mb-8 shadow-sm">
+      <h2 className="text-xl font-semibold text-slate-700 border-b pb-2 mb-4">
+        サーバーの設定
+      </h2>
+
+      <div className="space-y-3 mb-6 text-sm text-gray-700">
+        <label className="flex items-center">
+          <input
+            type="checkbox"
+            checked={draft.auto_start}
+            onChange={(e) => update({ auto_start: e.target.checked })}
+            className="rounded border-gray-300 text-blue-600"
+          />
+          <span className="ml-2">アプリの起動時にサーバーを起動する</span>
+        </label>
+
+        <label className="flex items-center">
+          <input
+            type="checkbox"
+            checked={draft.auth_enabled}
+            onChange={(e) => update({ auth_enabled: e.target.checked })}
+            className="rounded border-gray-300 text-blue-600"
+          />
+          <span className="ml-2">認証を有効にする</span>
+        </label>
+
+        <label className="flex items-center justify-between">
+          <span>前回のポート（空欄の場合は設定のポート）</span>
+          <input
+            type="number"
+            value={draft.last_port ?? ""}
+            onChange={(e) => update({ last_port: parseOptionalNumber(e.target.value) })}
+            className={inputClass("last_port")}
+          />
+        </label>
+
+        <label className="flex items-center">
+          <input
+            type="checkbox"
+            checked={draft.log_persist}
+            onChange={(e) => update({ log_persist: e.target.checked })}
+            className="rounded border-gray-300 text-blue-600"
+          />
+          <span className="ml-2">ログをファイルに保存する</span>
+        </label>
+
+        <label className="flex items-center justify-between">
+          <span>メモリに保持するログの件数</span>
+          <input
+            type="number"
+            value={draft.log_memory_entries}
+            onChange={(e) => update({ log_memory_entries: Number(e.target.value) })}
+            className={inputClass("log_memory_entries")}
+          />
+        </label>
+
+        <label className="flex items-center justify-between">
+          <span>保持するログファイルの数</span>
+          <input
+            type="number"
+            value={draft.log_retention_files}
+            onChange={(e) => update({ log_retention_files: Number(e.target.value) })}
+            className={inputClass("log_retention_files")}
+          />
+        </label>
+
+        <label className="flex items-center justify-between">
+          <span>ログファイルをローテーションする秒数（空欄の場合はしない）</span>
+          <input
+            type="number"
+            value={draft.log_max_file_age_secs ?? ""}
+            onChange={(e) => update({ log_max_file_age_secs: parseOptionalNumber(e.target.value) })}
+            className={inputClass("log_max_file_age_secs")}
+          />
+        </label>
+      </div>
+
+      <div className="flex items-center gap-4">
+        <button
+          onClick={save}
+          disabled={saving}
+          className="bg-blue-500 hover:bg-blue-600 text-white px-6 py-2 rounded disabled:bg-gray-400 disabled:cursor-not-allowed transition-colors"
+        >
+          保存
+        </button>
+        {message && <span className="text-sm text-green-600">{message}</span>}
+      </div>
+
+      {error && (
+        <div className="mt-4 p-4 bg-red-50 text-red-700 rounded border-l-4 border-red-700">
+          {error}
+        </div>
+      )}
+    </div>
+  );
+};
+
+export default ServerSettingsPanel;
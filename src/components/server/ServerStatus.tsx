@@ -2,18 +2,24 @@ import React from 'react';
 
 interface ServerStatusProps {
   isRunning: boolean;
+  isStopping: boolean;
   serverUrl: string;
+  tlsFingerprint: string | null;
   errorMessage: string;
   onStart: () => void;
   onStop: () => void;
+  onRestart: () => void;
 }
 
 const ServerStatus: React.FC<ServerStatusProps> = ({
   isRunning,
+  isStopping,
   serverUrl,
+  tlsFingerprint,
   errorMessage,
   onStart,
-  onStop
+  onStop,
+  onRestart
 }) => {
   return (
     <div className="bg-gray-50 rounded-lg p-6 mb-8 shadow-sm">
@@ -26,14 +32,21 @@ const ServerStatus: React.FC<ServerStatusProps> = ({
           ●
         </span>
         <span className="font-medium">
-          {isRunning ? "起動中" : "停止中"}
+          {isStopping ? "停止処理中（処理中のリクエストの完了を待っています）" : isRunning ? "起動中" : "停止中"}
         </span>
       </p>
       
-      <p className="mb-6">
+      <p className={tlsFingerprint ? "mb-2" : "mb-6"}>
         <span className="font-medium">URL:</span> {serverUrl}
       </p>
 
+      {tlsFingerprint && (
+        <p className="mb-6 text-sm break-all">
+          <span className="font-medium">証明書 (SHA-256):</span>{" "}
+          <code className="font-mono text-gray-600">{tlsFingerprint}</code>
+        </p>
+      )}
+
       <div className="flex gap-4 mb-6">
         <button
           onClick={onStart}
@@ -49,6 +62,13 @@ const ServerStatus: React.FC<ServerStatusProps> = ({
         >
           停止
         </button>
+        <button
+          onClick={onRestart}
+          disabled={!isRunning || isStopping}
+          className="bg-blue-500 hover:bg-blue-600 text-white px-6 py-2 rounded disabled:bg-gray-400 disabled:cursor-not-allowed transition-colors"
+        >
+          再起動
+        </button>
       </div>
 
       {errorMessage && (
@@ -2,6 +2,7 @@ import { useState, useEffect, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { Note } from '../types/notes';
+import { errorMessage } from '../types/error';
 
 // メモ管理のためのカスタムフック
 export function useNotes() {
@@ -10,6 +11,8 @@ export function useNotes() {
   const [noteTitle, setNoteTitle] = useState("");
   const [noteContent, setNoteContent] = useState("");
   const [editMode, setEditMode] = useState(false);
+  // 作成・更新・取得に失敗した場合のメッセージ
+  const [noteError, setNoteError] = useState("");
   
   // 選択中のノートIDを追跡する参照 - これをイベントリスナー内で使用
   const selectedNoteIdRef = useRef<string | null>(null);
@@ -25,7 +28,7 @@ export function useNotes() {
       const fetchedNotes = await invoke<Note[]>("list_notes");
       setNotes(fetchedNotes || []);
     } catch (error) {
-      console.error("Failed to fetch notes:", error);
+      setNoteError(`メモの取得中にエラーが発生しました: ${errorMessage(error)}`);
     }
   }, []);
   
@@ -86,6 +89,7 @@ export function useNotes() {
     setNoteTitle(note.title);
     setNoteContent(note.content);
     setEditMode(false);
+    setNoteError("");
   }, []);
 
   // 新規メモ作成モードに切り替え
@@ -94,6 +98,7 @@ export function useNotes() {
     setNoteTitle("");
     setNoteContent("");
     setEditMode(true);
+    setNoteError("");
   }, []);
 
   // 選択中のメモを編集モードに切り替え
@@ -113,6 +118,7 @@ export function useNotes() {
       setNoteContent("");
     }
     setEditMode(false);
+    setNoteError("");
   }, [selectedNote]);
 
   // 新規メモを作成
@@ -123,6 +129,7 @@ export function useNotes() {
     }
 
     try {
+      setNoteError("");
       await invoke<Note>("create_note", {
         title: noteTitle,
         content: noteContent,
@@ -136,7 +143,7 @@ export function useNotes() {
       setNoteContent("");
       setEditMode(false);
     } catch (error) {
-      console.error("Failed to create note:", error);
+      setNoteError(`メモの作成中にエラーが発生しました: ${errorMessage(error)}`);
     }
   }, [noteTitle, noteContent, fetchNotes]);
 
@@ -145,10 +152,13 @@ export function useNotes() {
     if (!selectedNote) return;
     
     try {
+      setNoteError("");
+      // 編集中にほかのクライアントが更新した場合は競合エラーになる
       await invoke<Note>("update_note", {
         id: selectedNote.id,
         title: noteTitle,
         content: noteContent,
+        expectedRevision: selectedNote.revision,
       });
       
       // メモのリストとメモの情報を更新
@@ -158,7 +168,7 @@ export function useNotes() {
       await fetchNotes();
       setEditMode(false);
     } catch (error) {
-      console.error("Failed to update note:", error);
+      setNoteError(`メモの更新中にエラーが発生しました: ${errorMessage(error)}`);
     }
   }, [selectedNote, noteTitle, noteContent, fetchNotes]);
 
@@ -191,7 +201,7 @@ export function useNotes() {
       }
     } catch (error) {
       console.error("Failed to delete note:", error);
-      alert(`メモの削除中にエラーが発生しました: ${error}`);
+      alert(`メモの削除中にエラーが発生しました: ${errorMessage(error)}`);
       return false;
     }
   }, [selectedNote, fetchNotes]);
@@ -207,6 +217,7 @@ export function useNotes() {
     noteTitle,
     noteContent,
     editMode,
+    noteError,
     setNoteTitle,
     setNoteContent,
     fetchNotes,
@@ -1,15 +1,52 @@
 import { useState, useEffect, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { LogEntry } from '../types/server';
+import { listen } from '@tauri-apps/api/event';
+import { AppError, errorMessage as toErrorMessage } from '../types/error';
+import { LogEntry, ReplayResult, ServerSettings, ServerStartedPayload, ServerStoppedPayload, ServerStoppingPayload } from '../types/server';
+
+// 画面に表示するログの最大件数
+const MAX_VISIBLE_LOGS = 500;
+
+// 通知されたエントリを反映（同じリクエストIDのエントリは置き換える）
+function mergeLogs(current: LogEntry[], entries: LogEntry[]): LogEntry[] {
+  const merged = [...current];
+  for (const entry of entries) {
+    const index = merged.findIndex((log) => log.request_id === entry.request_id);
+    if (index >= 0) {
+      merged[index] = entry;
+    } else {
+      merged.push(entry);
+    }
+  }
+  return merged.slice(-MAX_VISIBLE_LOGS);
+}
+
+// 起動の失敗を種類ごとに表示（ポートの使用中と設定の誤りを区別する）
+function startErrorMessage(action: string, error: unknown): string {
+  const appError = error as AppError;
+  if (appError?.kind === 'unavailable') {
+    return `Failed to ${action} server (port unavailable): ${toErrorMessage(error)}`;
+  }
+  if (appError?.kind === 'validation' && typeof appError.data?.field === 'string') {
+    return `Failed to ${action} server (check ${appError.data.field}): ${toErrorMessage(error)}`;
+  }
+  return `Failed to ${action} server: ${toErrorMessage(error)}`;
+}
 
 // サーバー管理のためのカスタムフック
 export function useServer() {
   const [serverRunning, setServerRunning] = useState(false);
+  // 処理中のリクエストの完了を待っている間は true
+  const [serverStopping, setServerStopping] = useState(false);
   const [serverUrl, setServerUrl] = useState("");
+  // HTTPS の証明書のフィンガープリント（HTTP の場合は null）
+  const [tlsFingerprint, setTlsFingerprint] = useState<string | null>(null);
   const [errorMessage, setErrorMessage] = useState("");
   const [logs, setLogs] = useState<LogEntry[]>([]);
   const [showLogs, setShowLogs] = useState(false);
   const [autoRefreshLogs, setAutoRefreshLogs] = useState(true);
+  const [replayResult, setReplayResult] = useState<ReplayResult | null>(null);
+  const [serverSettings, setServerSettings] = useState<ServerSettings | null>(null);
 
   // サーバーステータスをチェック
   const checkServerStatus = useCallback(async () => {
@@ -22,7 +59,7 @@ export function useServer() {
         await fetchLogs();
       }
     } catch (error) {
-      setErrorMessage(`Failed to check server status: ${error}`);
+      setErrorMessage(`Failed to check server status: ${toErrorMessage(error)}`);
     }
   }, []);
 
@@ -31,8 +68,9 @@ export function useServer() {
     try {
       const url = await invoke<string>("get_server_url");
       setServerUrl(url);
+      setTlsFingerprint(await invoke<string | null>("get_tls_fingerprint"));
     } catch (error) {
-      setErrorMessage(`Failed to get server URL: ${error}`);
+      setErrorMessage(`Failed to get server URL: ${toErrorMessage(error)}`);
     }
   }, []);
 
@@ -42,7 +80,7 @@ export function useServer() {
       const logsData = await invoke<LogEntry[]>("get_logs");
       setLogs(logsData);
     } catch (error) {
-      console.error("Failed to fetch logs:", error);
+      setErrorMessage(`Failed to fetch logs: ${toErrorMessage(error)}`);
     }
   }, []);
 
@@ -52,21 +90,46 @@ export function useServer() {
       await invoke("clear_logs");
       setLogs([]);
     } catch (error) {
-      console.error("Failed to clear logs:", error);
+      setErrorMessage(`Failed to clear logs: ${toErrorMessage(error)}`);
+    }
+  }, []);
+
+  // 記録されたリクエストを再実行（データを変更するメソッドは confirm が必要）
+  // エラーは呼び出し元で確認ダイアログの表示に使うため、そのまま投げる
+  const replayLog = useCallback(async (requestId: string, confirm = false) => {
+    const result = await invoke<ReplayResult>("replay_log_entry", { requestId, confirm });
+    setReplayResult(result);
+    return result;
+  }, []);
+
+  // 保存されたサーバーの設定を取得
+  const fetchServerSettings = useCallback(async () => {
+    try {
+      setServerSettings(await invoke<ServerSettings>("get_server_settings"));
+    } catch (error) {
+      setErrorMessage(`Failed to fetch server settings: ${toErrorMessage(error)}`);
     }
   }, []);
 
+  // サーバーの設定を保存（検証エラーは呼び出し元で項目ごとに表示するため、そのまま投げる）
+  const saveServerSettings = useCallback(async (settings: ServerSettings) => {
+    const saved = await invoke<ServerSettings>("set_server_settings", { settings });
+    setServerSettings(saved);
+    return saved;
+  }, []);
+
   // サーバーを起動
   const startServer = useCallback(async () => {
     try {
       setErrorMessage("");
       await invoke("start_server");
       setServerRunning(true);
+      await getServerUrl();
       await fetchLogs();
     } catch (error) {
-      setErrorMessage(`Failed to start server: ${error}`);
+      setErrorMessage(startErrorMessage("start", error));
     }
-  }, [fetchLogs]);
+  }, [fetchLogs, getServerUrl]);
 
   // サーバーを停止
   const stopServer = useCallback(async () => {
@@ -74,10 +137,24 @@ export function useServer() {
       setErrorMessage("");
       await invoke("stop_server");
       setServerRunning(false);
+      await getServerUrl();
     } catch (error) {
-      setErrorMessage(`Failed to stop server: ${error}`);
+      setErrorMessage(`Failed to stop server: ${toErrorMessage(error)}`);
     }
-  }, []);
+  }, [getServerUrl]);
+
+  // サーバーを再起動（停止中の場合はそのまま起動）
+  const restartServer = useCallback(async () => {
+    try {
+      setErrorMessage("");
+      await invoke("restart_server");
+      setServerRunning(true);
+      await getServerUrl();
+    } catch (error) {
+      setServerRunning(false);
+      setErrorMessage(startErrorMessage("restart", error));
+    }
+  }, [getServerUrl]);
 
   // タイムスタンプをフォーマットする関数
   const formatTimestamp = useCallback((timestamp: number) => {
@@ -99,28 +176,120 @@ export function useServer() {
   useEffect(() => {
     checkServerStatus();
     getServerUrl();
-  }, [checkServerStatus, getServerUrl]);
+    fetchServerSettings();
+  }, [checkServerStatus, getServerUrl, fetchServerSettings]);
+
+  // サーバー起動イベントで実際にバインドされたURLを反映
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    async function setupEventListener() {
+      try {
+        unlisten = await listen<ServerStartedPayload>('server-started', (event) => {
+          if (event.payload.url) {
+            setServerUrl(event.payload.url);
+          }
+          setTlsFingerprint(event.payload.tls_fingerprint);
+          setServerRunning(true);
+          // 起動したポートが last_port として保存される
+          const port = event.payload.port;
+          if (port !== null) {
+            setServerSettings((current) => current && { ...current, last_port: port });
+          }
+        });
+      } catch (error) {
+        console.error('Failed to set up server-started event listener:', error);
+      }
+    }
 
-  // ログの自動更新
+    setupEventListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, []);
+
+  // サーバーの停止処理（stop_server・restart_server・設定変更・アプリの終了）を反映
   useEffect(() => {
-    let interval: number | null = null;
-    
-    if (serverRunning && autoRefreshLogs) {
-      interval = window.setInterval(() => {
-        fetchLogs();
-      }, 2000); // 2秒ごとに更新
+    const unlisteners: Array<() => void> = [];
+    let disposed = false;
+
+    async function setupEventListeners() {
+      try {
+        const unlistenStopping = await listen<ServerStoppingPayload>('server-stopping', () => {
+          setServerStopping(true);
+        });
+        const unlistenStopped = await listen<ServerStoppedPayload>('server-stopped', (event) => {
+          setServerStopping(false);
+          setServerRunning(false);
+          if (event.payload.forced) {
+            console.warn(`Server stopped with ${event.payload.unfinished_requests} unfinished request(s)`);
+          }
+        });
+        unlisteners.push(unlistenStopping, unlistenStopped);
+
+        if (disposed) {
+          unlisteners.forEach((unlisten) => unlisten());
+        }
+      } catch (error) {
+        console.error('Failed to set up server-stopped event listeners:', error);
+      }
     }
 
+    setupEventListeners();
+
     return () => {
-      if (interval !== null) {
-        clearInterval(interval);
+      disposed = true;
+      unlisteners.forEach((unlisten) => unlisten());
+    };
+  }, []);
+
+  // ログの自動更新（server-log-appended / server-logs-cleared イベントで反映）
+  useEffect(() => {
+    if (!autoRefreshLogs) {
+      return;
+    }
+
+    const unlisteners: Array<() => void> = [];
+    let disposed = false;
+
+    async function setupEventListeners() {
+      try {
+        const unlistenAppended = await listen<LogEntry[]>('server-log-appended', (event) => {
+          setLogs((current) => mergeLogs(current, event.payload));
+        });
+        const unlistenCleared = await listen('server-logs-cleared', () => {
+          setLogs([]);
+        });
+        unlisteners.push(unlistenAppended, unlistenCleared);
+
+        if (disposed) {
+          unlisteners.forEach((unlisten) => unlisten());
+          return;
+        }
+
+        // 一時停止中に記録されたログを取得
+        await fetchLogs();
+      } catch (error) {
+        console.error('Failed to set up log event listeners:', error);
       }
+    }
+
+    setupEventListeners();
+
+    return () => {
+      disposed = true;
+      unlisteners.forEach((unlisten) => unlisten());
     };
-  }, [serverRunning, autoRefreshLogs, fetchLogs]);
+  }, [autoRefreshLogs, fetchLogs]);
 
   return {
     serverRunning,
+    serverStopping,
     serverUrl,
+    tlsFingerprint,
     errorMessage,
     logs,
     showLogs,
@@ -131,8 +300,15 @@ export function useServer() {
     getServerUrl,
     fetchLogs,
     clearLogs,
+    replayResult,
+    setReplayResult,
+    replayLog,
+    serverSettings,
+    fetchServerSettings,
+    saveServerSettings,
     startServer,
     stopServer,
+    restartServer,
     formatTimestamp,
     formatJSON
   };
@@ -0,0 +1,24 @@
+// Tauri コマンドと JSON-RPC で共通のエラー
+export type AppErrorKind =
+  | 'not_found'
+  | 'validation'
+  | 'storage'
+  | 'conflict'
+  | 'unavailable'
+  | 'rate_limited'
+  | 'internal';
+
+export interface AppError {
+  kind: AppErrorKind;
+  code: number;
+  message: string;
+  data: Record<string, unknown>;
+}
+
+// invoke が返したエラーを表示用の文字列に変換
+export function errorMessage(error: unknown): string {
+  if (typeof error === 'object' && error !== null && 'message' in error) {
+    return String((error as AppError).message);
+  }
+  return String(error);
+}
@@ -5,4 +5,12 @@ export interface Note {
   content: string;
   created_at: number;
   updated_at: number;
+  // 更新のたびに増える版番号
+  revision: number;
+}
+
+// notes-changed イベントと notes_changed 通知の内容
+export interface NoteChange {
+  id: string;
+  kind: 'created' | 'updated' | 'deleted';
 }
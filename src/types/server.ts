@@ -1,9 +1,127 @@
 // ログエントリの型定義
 export interface LogEntry {
+  // リクエストID（HTTP レスポンスの X-Request-Id ヘッダーと同じ値）
+  request_id: string;
+  // クライアントが X-Request-Id で指定した ID（HTTP レスポンスの X-Client-Request-Id ヘッダーと同じ値）
+  client_request_id: string | null;
   timestamp: number;
   method: string;
   uri: string;
-  headers: string;
+  // HTTP のリクエストのスキーム（http / https）。WebSocket・IPC では null
+  scheme: string | null;
+  // 受信した順のリクエストヘッダー
+  headers: LogHeader[];
   body: string | null;
   response: string | null;
+  status: number | null;
+  token_id: string | null;
+  // リクエストの Origin ヘッダー
+  origin: string | null;
+  // JSON-RPC の id とメソッド名（単一の呼び出しの場合）
+  rpc_id: string | number | null;
+  rpc_method: string | null;
+  // 切り捨て前の本文のバイト数
+  body_size: number | null;
+  response_size: number | null;
+  // 本文がログの上限を超えて切り捨てられたか
+  body_truncated: boolean;
+  response_truncated: boolean;
+  elapsed_ms: number | null;
+  // バッチリクエストの各呼び出し（単一の呼び出しでは空）
+  items: BatchItem[];
+  // ログから再実行したリクエストの場合は元のリクエストID
+  replay_of: string | null;
+}
+
+export interface LogHeader {
+  name: string;
+  value: string;
+}
+
+// バッチリクエストに含まれる1件の呼び出し
+export interface BatchItem {
+  index: number;
+  rpc_id: string | number | null;
+  rpc_method: string | null;
+  params: string | null;
+  result: string | null;
+  error: { code: number; message: string; data?: unknown } | null;
+  // パラメータまたは結果がログの上限を超えて切り捨てられたか
+  truncated: boolean;
+  elapsed_ms: number | null;
+}
+
+// query_logs コマンドの検索条件（省略した条件は無視される）
+export interface LogQuery {
+  from?: number | null;
+  to?: number | null;
+  method?: string | null;
+  rpc_method?: string | null;
+  status?: number | null;
+  error_code?: number | null;
+  text?: string | null;
+  token_id?: string | null;
+  origin?: string | null;
+  order?: "newest_first" | "oldest_first" | null;
+  offset?: number | null;
+  limit?: number | null;
+}
+
+// get_log_history・query_logs コマンドの戻り値
+export interface LogPage {
+  entries: LogEntry[];
+  // 続きがある場合に次のページの取得に使う offset
+  next_offset: number | null;
+}
+
+// replay_log_entry コマンドの戻り値
+export interface ReplayResult {
+  // 再実行のリクエストID
+  request_id: string;
+  replay_of: string;
+  response: unknown;
+  original_response: unknown;
+  // 元のレスポンスと一致したか（元のレスポンスが記録されていない場合は null）
+  matches: boolean | null;
+  // 値が異なる箇所の JSON Pointer
+  differences: string[];
+}
+
+// server-started イベントのペイロード
+export interface ServerStartedPayload {
+  url: string | null;
+  port: number | null;
+  ws_url: string | null;
+  ipc_path: string | null;
+  // HTTPS の場合の証明書の SHA-256 フィンガープリント
+  tls_fingerprint: string | null;
+}
+
+// server-stopping イベントのペイロード
+export interface ServerStoppingPayload {
+  // 処理中のリクエストの完了を待つ最大秒数
+  timeout_secs: number;
+  in_flight: number;
+}
+
+// server-stopped イベントのペイロード
+export interface ServerStoppedPayload {
+  // 処理中のリクエストが時間内に終わらず、強制的に閉じたか
+  forced: boolean;
+  unfinished_requests: number;
+}
+
+// get_server_settings・set_server_settings コマンドの設定
+export interface ServerSettings {
+  // アプリの起動時にサーバーを起動する
+  auto_start: boolean;
+  // 最後に起動したポート（次回の起動時に最初に試す。null の場合は設定のポート）
+  last_port: number | null;
+  auth_enabled: boolean;
+  // ログの保持
+  log_persist: boolean;
+  log_memory_entries: number;
+  log_retention_files: number;
+  // null の場合は経過時間でローテーションしない
+  log_max_file_age_secs: number | null;
 }